@@ -0,0 +1,112 @@
+extern crate pemmican;
+extern crate hyper;
+extern crate futures;
+extern crate chashmap;
+#[macro_use] extern crate criterion;
+
+use std::io::Error as IoError;
+use std::ops::Deref;
+use std::sync::Arc;
+use chashmap::CHashMap;
+use criterion::{BatchSize, Criterion};
+use futures::Future;
+use hyper::Method;
+use hyper::server::Request;
+use pemmican::{PluginData, Plugin, Shared};
+use pemmican::plugins::Router;
+
+type Handler = fn(data: PluginData<()>)
+                  -> Box<dyn Future<Item = PluginData<()>, Error = IoError>>;
+
+// The router as it was before routes were compiled into a trie, for comparison.
+struct HashRouter {
+    routes: CHashMap<(String, Method), Handler>,
+}
+
+impl Plugin<(), IoError> for HashRouter {
+    fn handle(&self, data: PluginData<()>)
+              -> Box<dyn Future<Item = PluginData<()>, Error = IoError>>
+    {
+        let path = data.request.path().to_owned();
+        let method = data.request.method().clone();
+        match self.routes.get_mut(&(path,method))
+        {
+            Some(guard) => {
+                let h = guard.deref();
+                (h)(data)
+            },
+            None => {
+                Box::new(::futures::future::ok(data))
+            }
+        }
+    }
+}
+
+fn handler(data: PluginData<()>)
+           -> Box<dyn Future<Item = PluginData<()>, Error = IoError>>
+{
+    Box::new(futures::future::ok( data ))
+}
+
+const PATHS: &[&str] = &[
+    "/", "/about", "/contact", "/blog", "/blog/archive", "/blog/feed.xml",
+    "/api/v1/users", "/api/v1/groups", "/api/v1/sessions", "/api/v2/users",
+    "/api/v2/groups", "/api/v2/sessions", "/help/faq", "/help/terms", "/help/privacy",
+];
+
+fn bench_static(c: &mut Criterion) {
+    let shared = Arc::new(Shared::new(1, ()));
+
+    let router = Router::new();
+    let hash_router = HashRouter { routes: CHashMap::new() };
+    for path in PATHS {
+        router.insert(path, Method::Get, handler);
+        hash_router.routes.insert((path.to_string(), Method::Get), handler as Handler);
+    }
+
+    let request = || Request::new(Method::Get, "/api/v2/groups".parse().unwrap());
+
+    let mut group = c.benchmark_group("static route");
+    group.bench_function("trie", |b| b.iter_batched(
+        || PluginData::new(shared.clone(), request()),
+        |data| router.handle(data).wait(),
+        BatchSize::SmallInput));
+    group.bench_function("chashmap", |b| b.iter_batched(
+        || PluginData::new(shared.clone(), request()),
+        |data| hash_router.handle(data).wait(),
+        BatchSize::SmallInput));
+    group.finish();
+}
+
+fn bench_patterns(c: &mut Criterion) {
+    let shared = Arc::new(Shared::new(1, ()));
+
+    let router = Router::new();
+    for path in PATHS {
+        router.insert(path, Method::Get, handler);
+    }
+    router.insert("/api/v2/users/:id", Method::Get, handler);
+    router.insert("/api/v2/users/:id/groups", Method::Get, handler);
+    router.insert("/static/**", Method::Get, handler);
+
+    let mut group = c.benchmark_group("pattern route");
+    group.bench_function("param", |b| b.iter_batched(
+        || PluginData::new(shared.clone(),
+                           Request::new(Method::Get, "/api/v2/users/42/groups".parse().unwrap())),
+        |data| router.handle(data).wait(),
+        BatchSize::SmallInput));
+    group.bench_function("wildcard", |b| b.iter_batched(
+        || PluginData::new(shared.clone(),
+                           Request::new(Method::Get, "/static/css/site.css".parse().unwrap())),
+        |data| router.handle(data).wait(),
+        BatchSize::SmallInput));
+    group.bench_function("miss", |b| b.iter_batched(
+        || PluginData::new(shared.clone(),
+                           Request::new(Method::Get, "/nowhere/at/all".parse().unwrap())),
+        |data| router.handle(data).wait(),
+        BatchSize::SmallInput));
+    group.finish();
+}
+
+criterion_group!(benches, bench_static, bench_patterns);
+criterion_main!(benches);
@@ -0,0 +1,44 @@
+//! Streaming response bodies.
+
+use std::io::Read;
+use futures::{stream, Future, Sink};
+use futures::sync::mpsc::SendError;
+use futures_cpupool::CpuPool;
+use hyper::{Body, Chunk};
+
+/// The size of the chunks read by `from_reader`
+pub const CHUNK_SIZE: usize = 64 * 1024;
+
+/// A body which streams from a reader, such as a `File`.
+///
+/// The reader is read on the thread pool, one chunk of up to `CHUNK_SIZE` bytes at
+/// a time.  The next chunk is not read until the previous one has been taken by
+/// the connection, so a slow client holds at most a couple of chunks in memory.
+///
+/// If reading fails, the error is logged and the body is cut short.  If the client
+/// goes away, reading stops.
+pub fn from_reader<R>(pool: &CpuPool, reader: R) -> Body
+    where R: Read + Send + 'static
+{
+    let (sender, body) = Body::pair();
+
+    let chunks = stream::unfold(Some(reader), |reader| {
+        let mut reader = reader?;
+        let mut buffer = vec![0; CHUNK_SIZE];
+        let item = match reader.read(&mut buffer) {
+            Ok(0) => return None,
+            Ok(len) => {
+                buffer.truncate(len);
+                (Ok(Chunk::from(buffer)), Some(reader))
+            },
+            Err(e) => {
+                warn!("Cannot read response body: {}", e);
+                (Err(::hyper::Error::from(e)), None)
+            },
+        };
+        Some(Ok::<_, SendError<Result<Chunk, ::hyper::Error>>>(item))
+    });
+
+    pool.spawn(sender.send_all(chunks).then(|_| Ok::<(), ()>(()))).forget();
+    body
+}
@@ -1,6 +1,11 @@
-
+use std::error::Error as StdError;
+use std::fmt;
+use std::io;
 use std::net::AddrParseError;
 use hyper::Error as HyperError;
+use hyper::StatusCode;
+use hyper::header::{ContentLength, ContentType};
+use hyper::server::Response;
 
 pub enum Error {
     Hyper(HyperError),
@@ -18,3 +23,107 @@ impl From<AddrParseError> for Error {
         Error::AddrParse(e)
     }
 }
+
+/// An error which should be answered with a particular status (such as 400 Bad
+/// Request), rather than with 500 Internal Server Error.
+///
+/// If a plugin fails with an `HttpError`, or with an error caused by one (see
+/// `find`), the server responds with its status and message.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HttpError {
+    status: StatusCode,
+    message: String,
+    /// Whether the response body is JSON rather than plain text
+    json: bool,
+}
+
+impl HttpError {
+    pub fn new(status: StatusCode, message: &str) -> HttpError {
+        HttpError {
+            status,
+            message: message.to_owned(),
+            json: false,
+        }
+    }
+
+    /// An error whose response body is a JSON object, `{"error": message}`, for
+    /// JSON APIs
+    #[cfg(feature = "json")]
+    pub fn json(status: StatusCode, message: &str) -> HttpError {
+        HttpError {
+            json: true,
+            ..HttpError::new(status, message)
+        }
+    }
+
+    /// A 400 Bad Request error
+    pub fn bad_request(message: &str) -> HttpError {
+        HttpError::new(StatusCode::BadRequest, message)
+    }
+
+    pub fn status(&self) -> StatusCode {
+        self.status
+    }
+
+    pub fn message(&self) -> &str {
+        &self.message
+    }
+
+    /// The response to send to the client: the status, with the message as a
+    /// plain text (or JSON) body
+    pub fn response(&self) -> Response {
+        let (content_type, body) = self.body();
+        Response::new()
+            .with_status(self.status)
+            .with_header(content_type)
+            .with_header(ContentLength(body.len() as u64))
+            .with_body(body)
+    }
+
+    #[cfg(feature = "json")]
+    fn body(&self) -> (ContentType, String) {
+        if self.json {
+            let body = serde_json::json!({ "error": self.message });
+            return (ContentType::json(), body.to_string());
+        }
+        (ContentType::plaintext(), self.message.clone())
+    }
+
+    #[cfg(not(feature = "json"))]
+    fn body(&self) -> (ContentType, String) {
+        (ContentType::plaintext(), self.message.clone())
+    }
+
+    /// Find an `HttpError` in an error or the chain of its sources.  The error
+    /// wrapped by an `io::Error` is looked at too.
+    pub fn find<'a>(error: &'a (dyn StdError + 'static)) -> Option<&'a HttpError> {
+        let mut current = Some(error);
+        while let Some(error) = current {
+            if let Some(http_error) = error.downcast_ref::<HttpError>() {
+                return Some(http_error);
+            }
+            let inner = error.downcast_ref::<io::Error>()
+                .and_then(|e| e.get_ref())
+                .and_then(|e| e.downcast_ref::<HttpError>());
+            if inner.is_some() {
+                return inner;
+            }
+            current = error.source();
+        }
+        None
+    }
+}
+
+impl fmt::Display for HttpError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: {}", self.status, self.message)
+    }
+}
+
+impl StdError for HttpError { }
+
+impl From<HttpError> for io::Error {
+    fn from(e: HttpError) -> io::Error {
+        io::Error::new(io::ErrorKind::InvalidInput, e)
+    }
+}
@@ -0,0 +1,76 @@
+//! Parsing of HTML form submissions: `application/x-www-form-urlencoded` bodies,
+//! and `multipart/form-data` bodies (which carry file uploads).
+//!
+//! Usually these are reached through `PluginData::form` and
+//! `PluginData::multipart`, which check the request `Content-Type` and take the
+//! request body.
+
+use std::path::PathBuf;
+use futures::{Future, Stream};
+use hyper::{Body, StatusCode};
+use crate::{HttpError, Query};
+
+mod multipart;
+pub use self::multipart::{Multipart, Part, PartData};
+
+/// Limits on the size of a form submission.  Exceeding them is a 413 Payload Too
+/// Large error.  `PluginData::json_body` holds JSON bodies to `max_total_size`.
+#[derive(Debug, Clone)]
+pub struct Limits {
+    /// The largest allowed value of a single field (or file)
+    pub max_field_size: u64,
+    /// The largest allowed body
+    pub max_total_size: u64,
+    /// Files larger than this are spooled to a temporary file, rather than kept
+    /// in memory.  Other fields are always kept in memory.
+    pub memory_threshold: u64,
+    /// Where temporary files are created, or None for the system default
+    pub temp_dir: Option<PathBuf>,
+}
+
+impl Default for Limits {
+    fn default() -> Limits {
+        Limits {
+            max_field_size: 16 * 1024 * 1024,
+            max_total_size: 64 * 1024 * 1024,
+            memory_threshold: 64 * 1024,
+            temp_dir: None,
+        }
+    }
+}
+
+fn too_large(what: &str) -> HttpError {
+    HttpError::new(StatusCode::PayloadTooLarge, &format!("{} is too large", what))
+}
+
+fn read_error(e: ::hyper::Error) -> HttpError {
+    HttpError::bad_request(&format!("Cannot read request body: {}", e))
+}
+
+/// Read and parse an `application/x-www-form-urlencoded` body.
+///
+/// The whole body must fit within `max_total_size`, and each value within
+/// `max_field_size`.
+pub fn urlencoded(body: Body, limits: &Limits)
+                  -> Box<dyn Future<Item = Query, Error = HttpError>>
+{
+    let max_total_size = limits.max_total_size;
+    let max_field_size = limits.max_field_size;
+    Box::new(
+        body.map_err(read_error)
+            .fold(Vec::new(), move |mut buffer, chunk| {
+                if (buffer.len() + chunk.len()) as u64 > max_total_size {
+                    return Err(too_large("Form"));
+                }
+                buffer.extend_from_slice(&chunk);
+                Ok(buffer)
+            })
+            .and_then(move |buffer| {
+                let query = Query::parse(&buffer);
+                if query.iter().any(|(_, value)| value.len() as u64 > max_field_size) {
+                    return Err(too_large("Form field"));
+                }
+                Ok(query)
+            })
+    )
+}
@@ -0,0 +1,353 @@
+use std::io::Write;
+use std::mem;
+use futures::{Async, Future, Poll, Stream};
+use futures_cpupool::{CpuFuture, CpuPool};
+use hyper::{Body, StatusCode};
+use memchr::memmem;
+use tempfile::NamedTempFile;
+use crate::HttpError;
+use crate::forms::{Limits, too_large, read_error};
+
+/// The longest allowed header section of a part
+const MAX_HEADERS_SIZE: usize = 8 * 1024;
+
+/// The contents of a multipart form field
+#[derive(Debug)]
+pub enum PartData {
+    Memory(Vec<u8>),
+    /// A file spooled to disk.  It is deleted when dropped, unless persisted.
+    File(NamedTempFile),
+}
+
+/// A field of a multipart form
+#[derive(Debug)]
+pub struct Part {
+    pub name: String,
+    /// The name of the uploaded file, if this is a file field
+    pub filename: Option<String>,
+    pub content_type: Option<String>,
+    /// The size of the contents
+    pub size: u64,
+    pub data: PartData,
+}
+
+impl Part {
+    /// The contents as text, if they are in memory and are UTF-8
+    pub fn text(&self) -> Option<&str> {
+        match self.data {
+            PartData::Memory(ref bytes) => ::std::str::from_utf8(bytes).ok(),
+            PartData::File(_) => None,
+        }
+    }
+}
+
+enum State {
+    /// Looking for the first boundary
+    Preamble,
+    /// Just past a boundary, which either ends the body or starts a part
+    Boundary,
+    Headers,
+    Contents(Part),
+    /// Writing contents of a part to its file within the threadpool, after which
+    /// the part is read (if the flag is set) or more of its contents are
+    Spooling(CpuFuture<Part, HttpError>, bool),
+    /// A part which has been completely read
+    Read(Part),
+    Done,
+}
+
+/// A stream of the fields of a `multipart/form-data` body, yielding each one once
+/// it has been completely read.
+///
+/// Files larger than `Limits::memory_threshold` are written to temporary files as
+/// they arrive.  Those writes block, so are made within the threadpool, and no
+/// more of the body is read until they are done.
+pub struct Multipart {
+    body: Body,
+    eof: bool,
+    /// The delimiter between parts: CRLF, `--`, and the boundary
+    delimiter: Vec<u8>,
+    buffer: Vec<u8>,
+    state: State,
+    total_size: u64,
+    limits: Limits,
+    pool: CpuPool,
+}
+
+impl Multipart {
+    /// Parse a body, whose parts are separated by `boundary` (from the
+    /// `Content-Type` header).  Files are spooled within `pool`.
+    pub fn new(body: Body, boundary: &str, limits: &Limits, pool: &CpuPool) -> Multipart {
+        Multipart {
+            body,
+            eof: false,
+            delimiter: format!("\r\n--{}", boundary).into_bytes(),
+            // So that a boundary at the very start matches the delimiter
+            buffer: b"\r\n".to_vec(),
+            state: State::Preamble,
+            total_size: 0,
+            limits: limits.clone(),
+            pool: pool.clone(),
+        }
+    }
+
+    /// Try to make progress with the data buffered so far.  Returns a part if
+    /// one was completed, or None if more data is needed.
+    fn parse(&mut self) -> Result<Option<Part>, HttpError> {
+        loop {
+            match mem::replace(&mut self.state, State::Done) {
+                State::Preamble => {
+                    match memmem::find(&self.buffer, &self.delimiter) {
+                        Some(i) => {
+                            self.buffer.drain(..i + self.delimiter.len());
+                            self.state = State::Boundary;
+                        },
+                        None => {
+                            let keep = self.delimiter.len() - 1;
+                            if self.buffer.len() > keep {
+                                let end = self.buffer.len() - keep;
+                                self.buffer.drain(..end);
+                            }
+                            self.state = State::Preamble;
+                            return Ok(None);
+                        }
+                    }
+                },
+                State::Boundary => {
+                    if self.buffer.len() < 2 {
+                        self.state = State::Boundary;
+                        return Ok(None);
+                    }
+                    if self.buffer.starts_with(b"--") {
+                        self.buffer.clear();
+                        return Ok(None);
+                    }
+                    if !self.buffer.starts_with(b"\r\n") {
+                        return Err(malformed("Bad boundary"));
+                    }
+                    self.buffer.drain(..2);
+                    self.state = State::Headers;
+                },
+                State::Headers => {
+                    let end = match memmem::find(&self.buffer, b"\r\n\r\n") {
+                        Some(end) => end,
+                        None if self.buffer.len() > MAX_HEADERS_SIZE => {
+                            return Err(malformed("Part headers are too long"));
+                        },
+                        None => {
+                            self.state = State::Headers;
+                            return Ok(None);
+                        }
+                    };
+                    let part = parse_headers(&self.buffer[..end])?;
+                    self.buffer.drain(..end + 4);
+                    self.state = State::Contents(part);
+                },
+                State::Contents(part) => {
+                    match memmem::find(&self.buffer, &self.delimiter) {
+                        Some(i) => {
+                            let rest = self.buffer.split_off(i + self.delimiter.len());
+                            self.buffer.truncate(i);
+                            let contents = mem::replace(&mut self.buffer, rest);
+                            self.state = self.append(part, contents, true)?;
+                        },
+                        None => {
+                            // Keep back anything which might be the start of the
+                            // delimiter
+                            let keep = self.delimiter.len() - 1;
+                            if self.buffer.len() <= keep {
+                                self.state = State::Contents(part);
+                                return Ok(None);
+                            }
+                            let rest = self.buffer.split_off(self.buffer.len() - keep);
+                            let contents = mem::replace(&mut self.buffer, rest);
+                            self.state = self.append(part, contents, false)?;
+                            if let State::Contents(_) = self.state {
+                                return Ok(None);
+                            }
+                        }
+                    }
+                },
+                State::Spooling(mut write, read) => {
+                    match write.poll()? {
+                        Async::Ready(part) if read => self.state = State::Read(part),
+                        Async::Ready(part) => self.state = State::Contents(part),
+                        Async::NotReady => {
+                            self.state = State::Spooling(write, read);
+                            return Ok(None);
+                        }
+                    }
+                },
+                State::Read(part) => {
+                    self.state = State::Boundary;
+                    return Ok(Some(part));
+                },
+                State::Done => return Ok(None),
+            }
+        }
+    }
+
+    /// Add contents to a part, which is then read if `read` is set.  Returns the
+    /// state to continue with.
+    fn append(&self, mut part: Part, mut contents: Vec<u8>, read: bool)
+              -> Result<State, HttpError>
+    {
+        part.size += contents.len() as u64;
+        if part.size > self.limits.max_field_size {
+            return Err(too_large(&format!("Field {:?}", part.name)));
+        }
+
+        let spool = match part.data {
+            PartData::Memory(ref mut bytes) => {
+                if !(part.filename.is_some() && part.size > self.limits.memory_threshold) {
+                    bytes.append(&mut contents);
+                    return Ok(if read { State::Read(part) } else { State::Contents(part) });
+                }
+                true
+            },
+            PartData::File(_) => false,
+        };
+
+        // Writing to the file blocks, so is done within the threadpool
+        let temp_dir = self.limits.temp_dir.clone();
+        let write = self.pool.spawn_fn(move|| {
+            if spool {
+                let file = match temp_dir {
+                    Some(ref dir) => NamedTempFile::new_in(dir),
+                    None => NamedTempFile::new(),
+                };
+                let mut file = file.map_err(spool_error)?;
+                if let PartData::Memory(ref bytes) = part.data {
+                    file.write_all(bytes).map_err(spool_error)?;
+                }
+                part.data = PartData::File(file);
+            }
+            if let PartData::File(ref mut file) = part.data {
+                file.write_all(&contents).map_err(spool_error)?;
+            }
+            Ok(part)
+        });
+        Ok(State::Spooling(write, read))
+    }
+}
+
+impl Stream for Multipart {
+    type Item = Part;
+    type Error = HttpError;
+
+    fn poll(&mut self) -> Poll<Option<Part>, HttpError> {
+        loop {
+            if let Some(part) = self.parse()? {
+                return Ok(Async::Ready(Some(part)));
+            }
+            match self.state {
+                State::Done => return Ok(Async::Ready(None)),
+                // Woken once the write is done
+                State::Spooling(..) => return Ok(Async::NotReady),
+                _ => { },
+            }
+            if self.eof {
+                return Err(malformed("Body ended early"));
+            }
+            match self.body.poll().map_err(read_error)? {
+                Async::Ready(Some(chunk)) => {
+                    self.total_size += chunk.len() as u64;
+                    if self.total_size > self.limits.max_total_size {
+                        return Err(too_large("Form"));
+                    }
+                    self.buffer.extend_from_slice(&chunk);
+                },
+                Async::Ready(None) => self.eof = true,
+                Async::NotReady => return Ok(Async::NotReady),
+            }
+        }
+    }
+}
+
+fn malformed(why: &str) -> HttpError {
+    HttpError::bad_request(&format!("Malformed multipart form: {}", why))
+}
+
+fn spool_error(e: ::std::io::Error) -> HttpError {
+    warn!("Cannot spool upload to a temporary file: {}", e);
+    HttpError::new(StatusCode::InternalServerError, "Cannot store upload")
+}
+
+/// Start a part from its headers
+fn parse_headers(headers: &[u8]) -> Result<Part, HttpError> {
+    let headers = ::std::str::from_utf8(headers)
+        .map_err(|_| malformed("Part headers are not UTF-8"))?;
+
+    let mut name = None;
+    let mut filename = None;
+    let mut content_type = None;
+    for line in headers.split("\r\n").filter(|line| !line.is_empty()) {
+        let (header, value) = match line.find(':') {
+            Some(i) => (line[..i].trim(), line[i + 1..].trim()),
+            None => return Err(malformed("Bad part header")),
+        };
+        if header.eq_ignore_ascii_case("Content-Disposition") {
+            for (param, value) in disposition_params(value) {
+                if param.eq_ignore_ascii_case("name") {
+                    name = Some(value);
+                } else if param.eq_ignore_ascii_case("filename") {
+                    filename = Some(value);
+                }
+            }
+        } else if header.eq_ignore_ascii_case("Content-Type") {
+            content_type = Some(value.to_owned());
+        }
+    }
+
+    Ok(Part {
+        name: name.ok_or_else(|| malformed("Part has no name"))?,
+        filename,
+        content_type,
+        size: 0,
+        data: PartData::Memory(Vec::new()),
+    })
+}
+
+/// The parameters of a `Content-Disposition` value, such as
+/// `form-data; name="file"; filename="a.txt"`.  Quoted values may contain `;`,
+/// and backslash escapes.
+fn disposition_params(value: &str) -> Vec<(String, String)> {
+    let mut params = Vec::new();
+    // Skip the disposition type
+    let mut rest = match value.find(';') {
+        Some(i) => &value[i + 1..],
+        None => return params,
+    };
+    loop {
+        rest = rest.trim_start();
+        let eq = match rest.find('=') {
+            Some(eq) => eq,
+            None => return params,
+        };
+        let param = rest[..eq].trim().to_owned();
+        rest = &rest[eq + 1..];
+
+        let mut value = String::new();
+        if let Some(quoted) = rest.strip_prefix('"') {
+            let mut chars = quoted.char_indices();
+            let mut end = quoted.len();
+            while let Some((i, c)) = chars.next() {
+                match c {
+                    '\\' => if let Some((_, c)) = chars.next() { value.push(c) },
+                    '"' => { end = i + 1; break; },
+                    c => value.push(c),
+                }
+            }
+            rest = &quoted[end..];
+            rest = match rest.find(';') {
+                Some(i) => &rest[i + 1..],
+                None => "",
+            };
+        } else {
+            let end = rest.find(';').unwrap_or(rest.len());
+            value.push_str(rest[..end].trim());
+            rest = if end < rest.len() { &rest[end + 1..] } else { "" };
+        }
+        params.push((param, value));
+    }
+}
@@ -8,7 +8,7 @@
 #[macro_use] extern crate log;
 
 pub mod error;
-pub use crate::error::Error;
+pub use crate::error::{Error, HttpError};
 
 pub mod config;
 pub use crate::config::Config;
@@ -16,6 +16,17 @@ pub use crate::config::Config;
 pub mod shared;
 pub use crate::shared::Shared;
 
+pub mod path;
+
+pub mod mime_types;
+
+pub mod body;
+
+pub mod query;
+pub use crate::query::Query;
+
+pub mod forms;
+
 pub mod plugins;
 pub use crate::plugins::{PluginData, Plugin};
 
@@ -49,8 +60,8 @@ impl<S, E> Pemmican<S, E>
     {
         let num_threads = config.num_threads;
         Pemmican {
-            config: config,
-            plugins: plugins,
+            config,
+            plugins,
             shared: Arc::new(Shared::new(num_threads, initial_state)),
         }
     }
@@ -84,12 +95,7 @@ impl<S, E> Service for Pemmican<S, E>
 
     fn call(&self, req: Request) -> Self::Future {
 
-        let data = PluginData {
-            shared: self.shared.clone(),
-            request: req,
-            response: Response::new().with_status(StatusCode::NotFound),
-            session_id: None,
-        };
+        let data = PluginData::new(self.shared.clone(), req);
 
         let mut fut: Box<dyn Future<Item = PluginData<S>, Error = E>> =
             Box::new(::futures::future::ok(data));
@@ -107,9 +113,14 @@ impl<S, E> Service for Pemmican<S, E>
         // Map future back to just a response
         let fut = Box::new( fut.map(|data| data.response) );
 
-        // any errors that remain are logged and InternalServerError
-        // is returned to the client
+        // errors caused by an HttpError are answered with its status. Any other
+        // errors that remain are logged and InternalServerError is returned to
+        // the client
         Box::new( fut.or_else(|e| {
+            if let Some(http_error) = HttpError::find(&e) {
+                debug!("error: {}", http_error);
+                return ::futures::future::ok(http_error.response());
+            }
             error!("error: {}", e);
             ::futures::future::ok(Response::new().with_status(StatusCode::InternalServerError))
         }))
@@ -0,0 +1,124 @@
+//! Guessing the media type of a file from its extension.
+
+use std::collections::HashMap;
+use std::path::Path;
+use std::sync::OnceLock;
+use hyper::mime::{self, Mime};
+
+/// The built-in table, as `(extension, media type)`.  Text types carry a UTF-8
+/// charset.
+const TYPES: &[(&str, &str)] = &[
+    // Documents
+    ("html", "text/html; charset=utf-8"),
+    ("htm", "text/html; charset=utf-8"),
+    ("xhtml", "application/xhtml+xml"),
+    ("txt", "text/plain; charset=utf-8"),
+    ("md", "text/markdown; charset=utf-8"),
+    ("csv", "text/csv; charset=utf-8"),
+    ("xml", "text/xml; charset=utf-8"),
+    ("rss", "application/rss+xml"),
+    ("atom", "application/atom+xml"),
+    ("pdf", "application/pdf"),
+
+    // Styles, scripts and data
+    ("css", "text/css; charset=utf-8"),
+    ("js", "text/javascript; charset=utf-8"),
+    ("mjs", "text/javascript; charset=utf-8"),
+    ("json", "application/json"),
+    ("map", "application/json"),
+    ("webmanifest", "application/manifest+json"),
+    ("wasm", "application/wasm"),
+
+    // Images
+    ("png", "image/png"),
+    ("jpg", "image/jpeg"),
+    ("jpeg", "image/jpeg"),
+    ("gif", "image/gif"),
+    ("webp", "image/webp"),
+    ("avif", "image/avif"),
+    ("svg", "image/svg+xml"),
+    ("ico", "image/x-icon"),
+    ("bmp", "image/bmp"),
+
+    // Fonts
+    ("woff", "font/woff"),
+    ("woff2", "font/woff2"),
+    ("ttf", "font/ttf"),
+    ("otf", "font/otf"),
+
+    // Audio and video
+    ("mp3", "audio/mpeg"),
+    ("ogg", "audio/ogg"),
+    ("wav", "audio/wav"),
+    ("mp4", "video/mp4"),
+    ("webm", "video/webm"),
+
+    // Archives
+    ("zip", "application/zip"),
+    ("gz", "application/gzip"),
+    ("tar", "application/x-tar"),
+];
+
+/// A table mapping file extensions to media types.
+///
+/// It starts out with the common types of the web (see `MimeTypes::new`), and can
+/// be extended with `insert`.  Extensions are matched case-insensitively.
+#[derive(Debug, Clone)]
+pub struct MimeTypes {
+    types: HashMap<String, Mime>,
+    default: Option<Mime>,
+}
+
+impl Default for MimeTypes {
+    fn default() -> MimeTypes {
+        let types = TYPES.iter()
+            .map(|&(extension, mime)| (extension.to_owned(), mime.parse().unwrap()))
+            .collect();
+        MimeTypes {
+            types,
+            default: Some(mime::APPLICATION_OCTET_STREAM),
+        }
+    }
+}
+
+impl MimeTypes {
+    /// The built-in table of common web types.  Unknown extensions are
+    /// `application/octet-stream`.
+    pub fn new() -> MimeTypes {
+        Default::default()
+    }
+
+    /// An empty table
+    pub fn empty() -> MimeTypes {
+        MimeTypes {
+            types: HashMap::new(),
+            default: Some(mime::APPLICATION_OCTET_STREAM),
+        }
+    }
+
+    /// Add or replace the type for an extension (without the leading `.`)
+    pub fn insert(&mut self, extension: &str, mime: Mime) {
+        self.types.insert(extension.to_ascii_lowercase(), mime);
+    }
+
+    /// Set the type of files whose extension is unknown, or None to leave their
+    /// type unsaid
+    pub fn set_default(&mut self, default: Option<Mime>) {
+        self.default = default;
+    }
+
+    /// The type of a file, from its extension
+    pub fn get(&self, path: &Path) -> Option<&Mime> {
+        path.extension()
+            .and_then(|e| e.to_str())
+            .and_then(|e| self.types.get(&*e.to_ascii_lowercase()))
+            .or(self.default.as_ref())
+    }
+}
+
+/// Guess the media type of a file from its extension, using the built-in table
+pub fn guess(path: &Path) -> Mime {
+    static TABLE: OnceLock<MimeTypes> = OnceLock::new();
+    let table = TABLE.get_or_init(MimeTypes::new);
+    table.get(path).cloned().unwrap_or(mime::APPLICATION_OCTET_STREAM)
+}
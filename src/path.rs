@@ -0,0 +1,133 @@
+//! Request path normalization, shared by the plugins which map paths to
+//! resources.
+
+use std::borrow::Cow;
+use percent_encoding::{utf8_percent_encode, AsciiSet, CONTROLS};
+
+/// Characters which must be percent-encoded within a path segment (RFC 3986
+/// `pchar`), including `/` itself
+pub(crate) const SEGMENT: &AsciiSet = &PATH.add(b'/');
+
+/// Characters which must be percent-encoded within a path
+pub(crate) const PATH: &AsciiSet = &CONTROLS
+    .add(b' ').add(b'"').add(b'#').add(b'%').add(b'<').add(b'>').add(b'?')
+    .add(b'[').add(b'\\').add(b']').add(b'^').add(b'`').add(b'{').add(b'|')
+    .add(b'}');
+
+/// How to treat a path which differs from a known path only by a trailing slash
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum TrailingSlash {
+    /// `/about` and `/about/` are different paths
+    #[default]
+    Strict,
+    /// Redirect with 308 Permanent Redirect to the known path.  Paths which only
+    /// normalize to a known path, such as `//about` or `/a/../about`, are
+    /// redirected to it too.
+    Redirect,
+    /// Treat both paths as the same
+    Equal,
+}
+
+/// Normalize a request path.
+///
+/// Percent-escapes are decoded, duplicate slashes are collapsed, and `.` and `..`
+/// segments are resolved (never climbing above the root).  A trailing slash is
+/// kept.
+///
+/// Returns None if the path cannot be decoded safely: if an escape is malformed,
+/// decodes to a `/` or a NUL, or the result is not UTF-8.
+///
+/// Paths which need no work are borrowed rather than copied.
+pub fn normalize(path: &str) -> Option<Cow<'_, str>> {
+    let decoded = if path.contains('%') {
+        Cow::Owned(decode(path)?)
+    } else {
+        Cow::Borrowed(path)
+    };
+
+    if !needs_cleaning(&decoded) {
+        return Some(decoded);
+    }
+    Some(Cow::Owned(clean(&decoded)))
+}
+
+/// Percent-encode a normalized path, so that it can be used in a URI again
+pub fn encode(path: &str) -> Cow<'_, str> {
+    utf8_percent_encode(path, PATH).into()
+}
+
+/// The same path with its trailing slash added or removed.  `/` has no
+/// alternative.
+pub fn toggle_trailing_slash(path: &str) -> Option<String> {
+    if path == "/" || path.is_empty() {
+        None
+    } else if let Some(trimmed) = path.strip_suffix('/') {
+        Some(trimmed.to_owned())
+    } else {
+        Some(format!("{}/", path))
+    }
+}
+
+fn decode(path: &str) -> Option<String> {
+    let bytes = path.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        if bytes[i] == b'%' {
+            let hi = hex_value(*bytes.get(i + 1)?)?;
+            let lo = hex_value(*bytes.get(i + 2)?)?;
+            let byte = hi * 16 + lo;
+            if byte == b'/' || byte == 0 {
+                return None;
+            }
+            decoded.push(byte);
+            i += 3;
+        } else {
+            decoded.push(bytes[i]);
+            i += 1;
+        }
+    }
+    String::from_utf8(decoded).ok()
+}
+
+fn hex_value(c: u8) -> Option<u8> {
+    match c {
+        b'0'..=b'9' => Some(c - b'0'),
+        b'a'..=b'f' => Some(c - b'a' + 10),
+        b'A'..=b'F' => Some(c - b'A' + 10),
+        _ => None,
+    }
+}
+
+fn needs_cleaning(path: &str) -> bool {
+    path.contains("//")
+        || path.split('/').any(|seg| seg == "." || seg == "..")
+}
+
+fn clean(path: &str) -> String {
+    let mut segments: Vec<&str> = Vec::new();
+    let mut trailing_slash = false;
+    for seg in path.split('/') {
+        match seg {
+            "" | "." => trailing_slash = true,
+            ".." => {
+                segments.pop();
+                trailing_slash = true;
+            },
+            seg => {
+                segments.push(seg);
+                trailing_slash = false;
+            },
+        }
+    }
+
+    let mut cleaned = String::with_capacity(path.len());
+    for seg in &segments {
+        cleaned.push('/');
+        cleaned.push_str(seg);
+    }
+    if trailing_slash || cleaned.is_empty() {
+        cleaned.push('/');
+    }
+    cleaned
+}
@@ -0,0 +1,41 @@
+use std::sync::Arc;
+use futures::Future;
+use crate::plugins::{Plugin, PluginData};
+
+/// This plugin runs a sequence of plugins, one after the other, just as Pemmican
+/// runs its own list of plugins.  It is useful for mounting a whole sub-chain of
+/// plugins on a single `Router` route.
+pub struct Chain<S, E> {
+    plugins: Vec<Arc<Box<dyn Plugin<S, E> + Send + Sync>>>,
+}
+
+impl<S, E> Chain<S, E> {
+    pub fn new(plugins: Vec<Arc<Box<dyn Plugin<S, E> + Send + Sync>>>) -> Chain<S, E> {
+        Chain {
+            plugins,
+        }
+    }
+}
+
+impl<S, E> Plugin<S, E> for Chain<S, E>
+    where S: 'static,
+          E: 'static
+{
+    fn handle(&self, data: PluginData<S>)
+              -> Box<dyn Future<Item = PluginData<S>, Error = E>>
+    {
+        let mut fut: Box<dyn Future<Item = PluginData<S>, Error = E>> =
+            Box::new(::futures::future::ok(data));
+
+        for plugin in &self.plugins {
+            let plug = plugin.clone();
+            fut = Box::new(
+                fut.and_then(move|data| {
+                    plug.handle(data)
+                })
+            );
+        }
+
+        fut
+    }
+}
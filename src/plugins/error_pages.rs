@@ -0,0 +1,135 @@
+use std::collections::HashMap;
+use std::io::Read;
+use std::path::{Component, Path, PathBuf};
+use std::sync::Arc;
+use futures::Future;
+use hyper::{Method, StatusCode};
+use hyper::header::{ContentLength, ContentType};
+use hyper::mime::Mime;
+use crate::mime_types;
+use crate::plugins::{Plugin, PluginData};
+use crate::plugins::htdocs::{Filesystem, Source};
+
+/// The page given for a status
+#[derive(Clone)]
+enum Page {
+    /// A file, by its path below the document root
+    File(PathBuf),
+    /// A template, with its content type
+    Inline(Mime, String),
+}
+
+/// This plugin gives error responses a page to show, such as `404.html`, in
+/// place of an empty body.
+///
+/// Pages are files from a document root (or any `Source`, see `with_source`), or
+/// inline templates.  A page is only given to responses with its status which
+/// have no body yet, and the status is kept.  A body is taken to be empty when
+/// there is none, or the `Content-Length` is 0.  Responses from
+/// `PluginData::not_found` have a plain text body, so keep it.
+///
+/// This plugin should be added at the end of your plugin chain.  Errors returned
+/// by plugins are answered after the chain has run, so do not get pages.
+pub struct ErrorPages {
+    source: Arc<dyn Source>,
+    pages: HashMap<u16, Page>,
+}
+
+impl ErrorPages {
+    /// Create an ErrorPages plugin whose files are in the given document root.
+    /// It has no pages until they are set.
+    pub fn new<P>(docroot: P) -> ErrorPages
+        where PathBuf: From<P>
+    {
+        ErrorPages::with_source(Filesystem::new(docroot))
+    }
+
+    /// Create an ErrorPages plugin whose files come from a source, such as the
+    /// same `Memory` an `Htdocs` serves
+    pub fn with_source<T: Source>(source: T) -> ErrorPages {
+        ErrorPages {
+            source: Arc::new(source),
+            pages: HashMap::new(),
+        }
+    }
+
+    /// Give responses with a status the file at `path` below the document root,
+    /// such as `404.html`.  Its `Content-Type` is guessed by its extension.
+    pub fn set_file(&mut self, status: StatusCode, path: &str) {
+        let path: PathBuf = Path::new(path).components()
+            .filter(|component| matches!(component, Component::Normal(_)))
+            .collect();
+        self.pages.insert(status.as_u16(), Page::File(path));
+    }
+
+    /// Give responses with a status a page rendered from a template, in which
+    /// `{status}` is replaced by the status code (such as `404`) and `{reason}`
+    /// by its reason phrase (such as `Not Found`).
+    pub fn set_inline(&mut self, status: StatusCode, content_type: Mime, template: &str) {
+        self.pages.insert(status.as_u16(), Page::Inline(content_type, template.to_owned()));
+    }
+
+    /// Stop giving responses with a status a page
+    pub fn remove(&mut self, status: StatusCode) {
+        self.pages.remove(&status.as_u16());
+    }
+}
+
+impl<S,E> Plugin<S,E> for ErrorPages
+    where S: Send + Sync + 'static,
+          E: Send + 'static
+{
+    fn handle(&self, mut data: PluginData<S>)
+        -> Box<dyn Future<Item = PluginData<S>, Error = E>>
+    {
+        let empty = match data.response.body_ref() {
+            Some(body) => body.is_empty()
+                || data.response.headers().get::<ContentLength>() == Some(&ContentLength(0)),
+            None => true,
+        };
+        if !empty {
+            return Box::new(::futures::future::ok(data));
+        }
+        let status = data.response.status();
+        let page = match self.pages.get(&status.as_u16()) {
+            Some(page) => page.clone(),
+            None => return Box::new(::futures::future::ok(data)),
+        };
+
+        match page {
+            Page::Inline(content_type, template) => {
+                let body = template
+                    .replace("{status}", &status.as_u16().to_string())
+                    .replace("{reason}", status.canonical_reason().unwrap_or(""));
+                set_page(&mut data, content_type, body.into_bytes());
+                Box::new(::futures::future::ok(data))
+            },
+            Page::File(path) => {
+                // Reading the file blocks, so is done within the threadpool
+                let source = self.source.clone();
+                Box::new(data.shared.pool.clone().spawn_fn(move|| {
+                    let read = source.open(&path).and_then(|(mut content, _)| {
+                        let mut body = Vec::new();
+                        content.read_to_end(&mut body).map(|_| body)
+                    });
+                    match read {
+                        Ok(body) => set_page(&mut data, mime_types::guess(&path), body),
+                        Err(e) => warn!("Cannot read error page {:?}: {}", path, e),
+                    }
+                    Ok(data)
+                }))
+            },
+        }
+    }
+}
+
+/// Set the body of a response to a page, keeping its status
+fn set_page<S>(data: &mut PluginData<S>, content_type: Mime, body: Vec<u8>) {
+    let head = data.request.method() == &Method::Head;
+    let headers = data.response.headers_mut();
+    headers.set(ContentType(content_type));
+    headers.set(ContentLength(body.len() as u64));
+    if !head {
+        data.response.set_body(body);
+    }
+}
@@ -9,15 +9,12 @@ header! { (XContentTypeOptions, "X-Content-Type-Options") => [String] }
 header! { (XFrameOptions, "X-Frame-Options") => [String] }
 header! { (XXssProtection, "X-Xss-Protection") => [String] }
 
+#[derive(Default)]
 pub enum GcReferrer {
+    #[default]
     NoReferrer,
     SameOrigin
 }
-impl Default for GcReferrer {
-    fn default() -> GcReferrer {
-        GcReferrer::NoReferrer
-    }
-}
 
 
 /// Good Citizen is a plugin that helps you treat users of the website with
@@ -0,0 +1,106 @@
+use std::path::{Component, Path};
+
+/// How Htdocs treats symbolic links under its document root.  This only matters
+/// for sources with links, such as `Filesystem`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Symlinks {
+    /// Follow all links, wherever they lead
+    Follow,
+    /// Serve nothing reached through a link
+    Deny,
+    /// Follow links only while they lead to somewhere under the document root
+    #[default]
+    WithinDocroot,
+}
+
+/// Which files under its document root Htdocs refuses to serve.  Requests for
+/// them are passed on as if the files did not exist.
+///
+/// By default, links leading out of the document root are not followed, and
+/// hidden files (those with a name starting with `.`, such as `.git/config` or
+/// `.env`) are not served, except for those under `.well-known`.
+#[derive(Debug, Clone)]
+pub struct Access {
+    pub symlinks: Symlinks,
+    /// Refuse files and directories whose names start with a `.`
+    pub deny_hidden: bool,
+    /// Glob patterns of files to refuse, where `*` matches any characters but `/`
+    /// and `?` matches any one character.  A pattern containing a `/` is matched
+    /// against the path below the document root and each of its directories, so
+    /// that `private/*` refuses everything under `private`; any other is matched
+    /// against each name in the path (such as `*.bak`).
+    pub deny: Vec<String>,
+}
+
+impl Default for Access {
+    fn default() -> Access {
+        Access {
+            symlinks: Symlinks::default(),
+            deny_hidden: true,
+            deny: Vec::new(),
+        }
+    }
+}
+
+impl Access {
+    /// Whether a file (or directory) may be served, by its path below the
+    /// document root.  Links are checked by the source (see
+    /// `Source::links_permitted`).
+    pub fn permits(&self, path: &Path) -> bool {
+        let names: Vec<&str> = match path.components()
+            .map(|component| match component {
+                Component::Normal(name) => name.to_str(),
+                _ => None,
+            })
+            .collect()
+        {
+            Some(names) => names,
+            None => return false,
+        };
+
+        if self.deny_hidden {
+            let hidden = names.iter().enumerate()
+                .any(|(i, name)| name.starts_with('.') && !(i == 0 && *name == ".well-known"));
+            if hidden {
+                return false;
+            }
+        }
+
+        for pattern in &self.deny {
+            let denied = if pattern.contains('/') {
+                let pattern = pattern.trim_start_matches('/');
+                (1..=names.len()).any(|n| glob(pattern, &names[..n].join("/")))
+            } else {
+                names.iter().any(|name| glob(pattern, name))
+            };
+            if denied {
+                return false;
+            }
+        }
+
+        true
+    }
+}
+
+/// Whether a glob pattern matches some text
+fn glob(pattern: &str, text: &str) -> bool {
+    let mut pattern = pattern.chars();
+    let mut text = text.chars();
+    match pattern.next() {
+        None => text.next().is_none(),
+        Some('*') => loop {
+            // Try each number of characters for the star to match, up to a `/`
+            if glob(pattern.as_str(), text.as_str()) {
+                return true;
+            }
+            match text.next() {
+                Some(c) if c != '/' => { },
+                _ => return false,
+            }
+        },
+        Some(p) => match text.next() {
+            Some(c) if c == p || (p == '?' && c != '/') => glob(pattern.as_str(), text.as_str()),
+            _ => false,
+        },
+    }
+}
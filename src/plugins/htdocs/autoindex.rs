@@ -0,0 +1,142 @@
+use std::fmt::Write;
+use std::time::{SystemTime, UNIX_EPOCH};
+use hyper::header::{Accept, HttpDate};
+use hyper::mime::{self, Mime};
+use hyper::Headers;
+use percent_encoding::utf8_percent_encode;
+use crate::path::SEGMENT;
+use super::source::Metadata;
+
+/// How Htdocs lists directories which have no index file
+#[derive(Debug, Clone, Default)]
+pub struct Autoindex {
+    /// Also offer the listing as JSON, to requests which prefer
+    /// `application/json` (by their `Accept` header)
+    pub json: bool,
+    /// List files whose names start with a `.`, if they are served at all (see
+    /// `Access`)
+    pub show_hidden: bool,
+}
+
+struct Entry {
+    name: String,
+    is_dir: bool,
+    size: u64,
+    modified: Option<SystemTime>,
+}
+
+impl Autoindex {
+    /// List the entries of a directory, for the request path `url_path` (which
+    /// ends in `/`).  Returns the content type and body.
+    pub fn render(&self, dir: Vec<(String, Metadata)>, url_path: &str, headers: &Headers)
+                  -> (Mime, String)
+    {
+        let mut entries: Vec<Entry> = dir.into_iter()
+            .filter(|(name, _)| !name.starts_with('.') || self.show_hidden)
+            .map(|(name, metadata)| Entry {
+                name,
+                is_dir: metadata.is_dir,
+                size: metadata.len,
+                modified: metadata.modified,
+            })
+            .collect();
+        // Directories first, then by name
+        entries.sort_by(|a, b| b.is_dir.cmp(&a.is_dir).then_with(|| a.name.cmp(&b.name)));
+
+        if self.json && prefers_json(headers) {
+            (mime::APPLICATION_JSON, json(&entries))
+        } else {
+            (mime::TEXT_HTML_UTF_8, html(&entries, url_path))
+        }
+    }
+}
+
+/// Whether the `Accept` header rates JSON above HTML
+fn prefers_json(headers: &Headers) -> bool {
+    let accept = match headers.get::<Accept>() {
+        Some(accept) => accept,
+        None => return false,
+    };
+    let quality = |wanted: &Mime| {
+        accept.iter()
+            .filter(|item| {
+                let range = &item.item;
+                (range.type_() == mime::STAR || range.type_() == wanted.type_())
+                    && (range.subtype() == mime::STAR || range.subtype() == wanted.subtype())
+            })
+            .map(|item| item.quality)
+            .max()
+    };
+    quality(&mime::APPLICATION_JSON) > quality(&mime::TEXT_HTML)
+}
+
+fn html(entries: &[Entry], url_path: &str) -> String {
+    let title = format!("Index of {}", escape_html(url_path));
+    let mut page = format!("<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n\
+                            <title>{title}</title>\n</head>\n<body>\n<h1>{title}</h1>\n\
+                            <table>\n<tr><th>Name</th><th>Size</th><th>Modified</th></tr>\n",
+                           title = title);
+    if url_path != "/" {
+        page.push_str("<tr><td><a href=\"../\">../</a></td><td></td><td></td></tr>\n");
+    }
+    for entry in entries {
+        let slash = if entry.is_dir { "/" } else { "" };
+        let size = if entry.is_dir { String::new() } else { entry.size.to_string() };
+        let modified = entry.modified.map(|m| HttpDate::from(m).to_string()).unwrap_or_default();
+        let _ = writeln!(page, "<tr><td><a href=\"./{href}{slash}\">{name}{slash}</a></td>\
+                                <td>{size}</td><td>{modified}</td></tr>",
+                         href = utf8_percent_encode(&entry.name, SEGMENT),
+                         name = escape_html(&entry.name), slash = slash, size = size,
+                         modified = modified);
+    }
+    page.push_str("</table>\n</body>\n</html>\n");
+    page
+}
+
+/// The listing as a JSON array of objects with `name`, `type` (`"file"` or
+/// `"directory"`), `size` and `modified` (seconds since the Unix epoch) fields
+fn json(entries: &[Entry]) -> String {
+    let mut json = String::from("[");
+    for (i, entry) in entries.iter().enumerate() {
+        if i > 0 {
+            json.push(',');
+        }
+        let modified = entry.modified
+            .and_then(|m| m.duration_since(UNIX_EPOCH).ok())
+            .map(|m| m.as_secs().to_string())
+            .unwrap_or_else(|| "null".to_owned());
+        let _ = write!(json, "{{\"name\":\"{}\",\"type\":\"{}\",\"size\":{},\"modified\":{}}}",
+                       escape_json(&entry.name), if entry.is_dir { "directory" } else { "file" },
+                       entry.size, modified);
+    }
+    json.push(']');
+    json
+}
+
+fn escape_html(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&#39;"),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+fn escape_json(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '"' => escaped.push_str("\\\""),
+            '\\' => escaped.push_str("\\\\"),
+            c if (c as u32) < 0x20 => { let _ = write!(escaped, "\\u{:04x}", c as u32); },
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
@@ -0,0 +1,143 @@
+use std::collections::{BTreeMap, HashMap};
+use std::io::{self, Cursor, Read, Seek, SeekFrom};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use super::source::{Content, Metadata, Source};
+
+/// Settings for an in-memory cache of the files Htdocs serves most often.
+///
+/// Small files are kept in memory once read, and served from there for as long
+/// as their modification time and size stay the same.  When the cache is full,
+/// the least recently served files are dropped first.
+#[derive(Debug, Clone)]
+pub struct Cache {
+    /// The largest file kept in the cache
+    pub max_file_size: u64,
+    /// The most bytes kept in the cache, over all files
+    pub max_total_size: u64,
+}
+
+impl Default for Cache {
+    fn default() -> Cache {
+        Cache {
+            max_file_size: 256 * 1024,
+            max_total_size: 16 * 1024 * 1024,
+        }
+    }
+}
+
+/// A cache of file contents, shared between requests
+pub struct FileCache {
+    settings: Cache,
+    entries: Mutex<Entries>,
+}
+
+struct Entries {
+    files: HashMap<PathBuf, Entry>,
+    /// The paths of the files, by when they were last used
+    used: BTreeMap<u64, PathBuf>,
+    /// Counts uses, to order them
+    clock: u64,
+    total_size: u64,
+}
+
+struct Entry {
+    bytes: Arc<[u8]>,
+    metadata: Metadata,
+    last_used: u64,
+}
+
+impl FileCache {
+    pub fn new(settings: Cache) -> FileCache {
+        FileCache {
+            settings,
+            entries: Mutex::new(Entries {
+                files: HashMap::new(),
+                used: BTreeMap::new(),
+                clock: 0,
+                total_size: 0,
+            }),
+        }
+    }
+
+    /// Open a file, from the cache if it is there and still fresh.  Otherwise it is
+    /// opened from the source, and read into the cache if it is small enough.
+    /// Files the source holds in memory already are not cached again.
+    pub fn open(&self, source: &dyn Source, path: &Path) -> io::Result<(Content, Metadata)> {
+        let metadata = source.metadata(path)?;
+        if let Some(bytes) = self.get(path, &metadata) {
+            return Ok((Content::Memory(Cursor::new(bytes)), metadata));
+        }
+
+        let (content, metadata) = source.open(path)?;
+        let mut file = match content {
+            Content::File(file) => file,
+            memory => return Ok((memory, metadata)),
+        };
+        if metadata.is_dir || metadata.len > self.settings.max_file_size {
+            return Ok((Content::File(file), metadata));
+        }
+        let mut bytes = Vec::with_capacity(metadata.len as usize);
+        file.read_to_end(&mut bytes)?;
+        if bytes.len() as u64 != metadata.len {
+            // The file changed while we read it, so serve it from disk
+            file.seek(SeekFrom::Start(0))?;
+            return Ok((Content::File(file), metadata));
+        }
+        let bytes: Arc<[u8]> = bytes.into();
+        self.insert(path, bytes.clone(), metadata.clone());
+        Ok((Content::Memory(Cursor::new(bytes)), metadata))
+    }
+
+    /// Get the cached bytes of a file, if its metadata still matches
+    fn get(&self, path: &Path, metadata: &Metadata) -> Option<Arc<[u8]>> {
+        let mut entries = self.entries.lock().unwrap();
+        let fresh = entries.files.get(path)?.metadata == *metadata;
+        if !fresh {
+            entries.remove(path);
+            return None;
+        }
+
+        entries.clock += 1;
+        let now = entries.clock;
+        let Entries { ref mut files, ref mut used, .. } = *entries;
+        let entry = files.get_mut(path)?;
+        used.remove(&entry.last_used);
+        used.insert(now, path.to_owned());
+        entry.last_used = now;
+        Some(entry.bytes.clone())
+    }
+
+    fn insert(&self, path: &Path, bytes: Arc<[u8]>, metadata: Metadata) {
+        let mut entries = self.entries.lock().unwrap();
+        entries.remove(path);
+
+        // A file larger than the whole cache would only empty it
+        let size = bytes.len() as u64;
+        if size > self.settings.max_total_size {
+            return;
+        }
+        while entries.total_size + size > self.settings.max_total_size {
+            let oldest = match entries.used.values().next() {
+                Some(oldest) => oldest.clone(),
+                None => return,
+            };
+            entries.remove(&oldest);
+        }
+
+        entries.clock += 1;
+        let now = entries.clock;
+        entries.used.insert(now, path.to_owned());
+        entries.total_size += size;
+        entries.files.insert(path.to_owned(), Entry { bytes, metadata, last_used: now });
+    }
+}
+
+impl Entries {
+    fn remove(&mut self, path: &Path) {
+        if let Some(entry) = self.files.remove(path) {
+            self.used.remove(&entry.last_used);
+            self.total_size -= entry.bytes.len() as u64;
+        }
+    }
+}
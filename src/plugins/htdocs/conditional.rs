@@ -0,0 +1,96 @@
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use hyper::{Headers, StatusCode};
+use hyper::header::{EntityTag, ETag, IfMatch, IfModifiedSince, IfNoneMatch,
+                    IfUnmodifiedSince, LastModified};
+use super::source::Metadata;
+
+/// Which kind of `ETag` Htdocs gives files
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ETags {
+    /// Strong tags, which (unlike weak ones) can validate range requests
+    Strong,
+    /// Weak tags, which only promise the file is equivalent, not identical
+    Weak,
+    /// No tags at all.  `Last-Modified` is still sent.
+    Off,
+}
+
+/// The validators of a file: its entity tag, and when it was last modified
+pub struct Validators {
+    pub etag: Option<EntityTag>,
+    /// When the file was last modified, truncated to whole seconds as that is the
+    /// precision of HTTP dates
+    pub last_modified: Option<SystemTime>,
+}
+
+impl Validators {
+    pub fn new(metadata: &Metadata, etags: ETags) -> Validators {
+        let modified = metadata.modified
+            .and_then(|m| m.duration_since(UNIX_EPOCH).ok());
+
+        let etag = modified.and_then(|modified| {
+            let tag = format!("{:x}.{:x}-{:x}", modified.as_secs(), modified.subsec_nanos(),
+                              metadata.len);
+            match etags {
+                ETags::Strong => Some(EntityTag::strong(tag)),
+                ETags::Weak => Some(EntityTag::weak(tag)),
+                ETags::Off => None,
+            }
+        });
+
+        Validators {
+            etag,
+            last_modified: modified.map(|m| UNIX_EPOCH + Duration::from_secs(m.as_secs())),
+        }
+    }
+
+    pub fn set_headers(&self, headers: &mut Headers) {
+        if let Some(ref etag) = self.etag {
+            headers.set(ETag(etag.clone()));
+        }
+        if let Some(last_modified) = self.last_modified {
+            headers.set(LastModified(last_modified.into()));
+        }
+    }
+
+    /// Evaluate the preconditions of a `GET` or `HEAD` request, in the order given
+    /// by RFC 7232 section 6.  Returns the status to respond with (304 Not
+    /// Modified or 412 Precondition Failed) if the file should not be sent.
+    pub fn evaluate(&self, headers: &Headers) -> Option<StatusCode> {
+        if let Some(if_match) = headers.get::<IfMatch>() {
+            let matches = match *if_match {
+                IfMatch::Any => true,
+                IfMatch::Items(ref tags) => {
+                    self.etag.as_ref().is_some_and(|etag| tags.iter().any(|t| t.strong_eq(etag)))
+                },
+            };
+            if !matches {
+                return Some(StatusCode::PreconditionFailed);
+            }
+        } else if let Some(IfUnmodifiedSince(date)) = headers.get::<IfUnmodifiedSince>() {
+            let since: SystemTime = (*date).into();
+            if self.last_modified.is_none_or(|modified| modified > since) {
+                return Some(StatusCode::PreconditionFailed);
+            }
+        }
+
+        if let Some(if_none_match) = headers.get::<IfNoneMatch>() {
+            let matches = match *if_none_match {
+                IfNoneMatch::Any => true,
+                IfNoneMatch::Items(ref tags) => {
+                    self.etag.as_ref().is_some_and(|etag| tags.iter().any(|t| t.weak_eq(etag)))
+                },
+            };
+            if matches {
+                return Some(StatusCode::NotModified);
+            }
+        } else if let Some(IfModifiedSince(date)) = headers.get::<IfModifiedSince>() {
+            let since: SystemTime = (*date).into();
+            if self.last_modified.is_some_and(|modified| modified <= since) {
+                return Some(StatusCode::NotModified);
+            }
+        }
+
+        None
+    }
+}
@@ -0,0 +1,69 @@
+use std::path::{Component, Path, PathBuf};
+use hyper::header::{Accept, q};
+use hyper::mime;
+use hyper::server::Request;
+use crate::path;
+
+/// A file Htdocs serves in place of files which do not exist, for single-page
+/// applications whose client-side router handles paths like
+/// `/app/settings/profile`.
+///
+/// The fallback is only served to requests which accept HTML (by listing
+/// `text/html` in their `Accept` header, as browsers navigating do), and not to
+/// requests for paths that look like assets, whose last segment has an
+/// extension (such as `/app.js`).  Those are passed on as before, so usually end
+/// in a 404.
+#[derive(Debug, Clone)]
+pub struct Fallback {
+    /// The file to serve, such as `index.html`
+    pub file: String,
+    /// Path prefixes, such as `/api`, under which the fallback is not served
+    pub exclude: Vec<String>,
+}
+
+impl Fallback {
+    /// A fallback to `file`, excluding no paths
+    pub fn new(file: &str) -> Fallback {
+        Fallback {
+            file: file.to_owned(),
+            exclude: Vec::new(),
+        }
+    }
+
+    /// Whether the fallback should be served for a request which matched no file
+    pub fn applies(&self, request: &Request) -> bool {
+        let url_path = match path::normalize(request.path()) {
+            Some(url_path) => url_path,
+            None => return false,
+        };
+        if Path::new(&*url_path).extension().is_some() {
+            return false;
+        }
+        let excluded = self.exclude.iter().any(|prefix| {
+            let prefix = prefix.trim_end_matches('/');
+            url_path.strip_prefix(prefix)
+                .is_some_and(|rest| rest.is_empty() || rest.starts_with('/'))
+        });
+        !excluded && accepts_html(request)
+    }
+
+    /// The path of the file to serve, below the document root
+    pub fn path(&self) -> PathBuf {
+        Path::new(&self.file).components()
+            .filter(|component| matches!(component, Component::Normal(_)))
+            .collect()
+    }
+}
+
+/// Whether the `Accept` header of a request lists HTML
+fn accepts_html(request: &Request) -> bool {
+    let accept = match request.headers().get::<Accept>() {
+        Some(accept) => accept,
+        None => return false,
+    };
+    accept.iter().any(|item| {
+        item.quality > q(0)
+            && item.item.type_() == mime::TEXT
+            && (item.item.subtype() == mime::HTML || item.item.subtype() == mime::STAR)
+    })
+}
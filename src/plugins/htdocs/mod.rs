@@ -0,0 +1,420 @@
+use std::path::{Path, PathBuf, Component};
+use std::io::{self, Read, Seek, SeekFrom};
+use std::sync::Arc;
+use futures::Future;
+use futures_cpupool::CpuPool;
+use hyper::{Method, StatusCode};
+use hyper::server::Request;
+use hyper::header::{AcceptRanges, ContentEncoding, ContentLength, ContentRange, ContentType,
+                    RangeUnit};
+use crate::{body, path};
+use crate::mime_types::MimeTypes;
+use crate::plugins::{Plugin, PluginData};
+
+mod conditional;
+pub use self::conditional::ETags;
+use self::conditional::Validators;
+
+mod ranges;
+use self::ranges::Ranges;
+
+mod autoindex;
+pub use self::autoindex::Autoindex;
+
+mod precompressed;
+use self::precompressed::Sibling;
+
+mod access;
+pub use self::access::{Access, Symlinks};
+
+mod source;
+pub use self::source::{Content, Filesystem, Memory, Metadata, Source};
+
+mod fallback;
+pub use self::fallback::Fallback;
+
+mod cache;
+pub use self::cache::Cache;
+use self::cache::FileCache;
+
+/// This plugin serves static files from a document root.
+///
+/// The files are usually in a directory on disk, but can come from any `Source`,
+/// such as files compiled into the program (see `with_source`).
+///
+/// Files are given a `Content-Type` by their extension; see `set_mime_types`.
+///
+/// Responses carry `Last-Modified` and `ETag` headers (see `set_etags`), and
+/// conditional requests (`If-None-Match`, `If-Modified-Since`, `If-Match` and
+/// `If-Unmodified-Since`) are answered with 304 Not Modified or 412 Precondition
+/// Failed as RFC 7232 describes.
+///
+/// Precompressed siblings of files (`.br` and `.gz`) can be served in their place;
+/// see `set_precompressed`.
+///
+/// Hidden files, and links leading out of the document root, are not served; see
+/// `set_access`.
+///
+/// Small files can be kept in memory, rather than read for every request; see
+/// `set_cache`.
+///
+/// Directories are served by their index file, or can be listed (see
+/// `set_autoindex`).
+///
+/// Single-page applications can have a file such as `index.html` served for
+/// paths which match no file; see `set_fallback`.
+///
+/// Byte range requests are supported, including requests for several ranges
+/// (which are answered with a `multipart/byteranges` body), and `If-Range`.
+///
+/// The request path is normalized first (see `path::normalize`), so escaped
+/// characters are decoded.  Requests whose paths cannot be decoded safely are
+/// passed on unhandled.
+///
+/// If a previous router handler has already set a body, this plugin
+/// will take no action.  It will only serve files if the path matches and
+/// the response body has not yet been set.
+pub struct Htdocs {
+    settings: Arc<Settings>,
+}
+
+/// The settings of an `Htdocs`, shared with the thread pool
+#[derive(Clone)]
+struct Settings {
+    source: Arc<dyn Source>,
+    index: Option<String>,
+    mime_types: MimeTypes,
+    etags: ETags,
+    autoindex: Option<Autoindex>,
+    precompressed: bool,
+    cache: Option<Arc<FileCache>>,
+    access: Access,
+    fallback: Option<Fallback>,
+}
+
+impl Htdocs {
+    /// Create a new Htdocs plugin with the given document root.
+    ///
+    /// `index` is the file to search for in case a directory is specified,
+    /// e.g. Some(`index.html`) or simply None if directories are not to be
+    /// matched.
+    pub fn new<P>(docroot: P, index: Option<String>) -> Htdocs
+        where PathBuf: From<P>
+    {
+        Htdocs::with_source(Filesystem::new(docroot), index)
+    }
+
+    /// Create a new Htdocs plugin serving the files of a source, such as a
+    /// `Memory` of assets compiled into the program.
+    pub fn with_source<T: Source>(source: T, index: Option<String>) -> Htdocs {
+        Htdocs {
+            settings: Arc::new(Settings {
+                source: Arc::new(source),
+                index,
+                mime_types: MimeTypes::new(),
+                etags: ETags::Strong,
+                autoindex: None,
+                precompressed: false,
+                cache: None,
+                access: Access::default(),
+                fallback: None,
+            }),
+        }
+    }
+
+    /// Set the table used to choose the `Content-Type` of files.  By default, it
+    /// is `MimeTypes::new()`.
+    pub fn set_mime_types(&mut self, mime_types: MimeTypes) {
+        Arc::make_mut(&mut self.settings).mime_types = mime_types;
+    }
+
+    /// Set which kind of `ETag` files are given.  By default they are strong,
+    /// derived from the file's size and modification time.
+    pub fn set_etags(&mut self, etags: ETags) {
+        Arc::make_mut(&mut self.settings).etags = etags;
+    }
+
+    /// Serve precompressed siblings of files, such as `app.js.br` or `app.js.gz`
+    /// for `app.js`, to clients which accept those encodings.  This is off by
+    /// default.
+    pub fn set_precompressed(&mut self, precompressed: bool) {
+        Arc::make_mut(&mut self.settings).precompressed = precompressed;
+    }
+
+    /// Keep small files in memory, or None (the default) to read files from disk
+    /// for every request.  Setting a cache empties any previous one.
+    pub fn set_cache(&mut self, cache: Option<Cache>) {
+        let cache = cache.map(|cache| Arc::new(FileCache::new(cache)));
+        Arc::make_mut(&mut self.settings).cache = cache;
+    }
+
+    /// Set which files are refused.  By default, hidden files and links leading
+    /// out of the document root are.
+    pub fn set_access(&mut self, access: Access) {
+        Arc::make_mut(&mut self.settings).access = access;
+    }
+
+    /// Serve a file in place of those which do not exist, for single-page
+    /// applications, or None (the default) to pass such requests on.
+    pub fn set_fallback(&mut self, fallback: Option<Fallback>) {
+        Arc::make_mut(&mut self.settings).fallback = fallback;
+    }
+
+    /// List directories which have no index file, or None (the default) to pass
+    /// such requests on to the next plugin.
+    pub fn set_autoindex(&mut self, autoindex: Option<Autoindex>) {
+        Arc::make_mut(&mut self.settings).autoindex = autoindex;
+    }
+}
+
+impl<S,E> Plugin<S,E> for Htdocs
+    where S: Send + Sync + 'static,
+          E: Send + 'static
+{
+    fn handle(&self, data: PluginData<S>)
+        -> Box<dyn Future<Item = PluginData<S>, Error = E>>
+    {
+        // Only handle GET and HEAD requests
+        match data.request.method() {
+            &Method::Get | &Method::Head => { },
+            _ => return Box::new(::futures::future::ok(data)),
+        }
+
+        let mut filepath = {
+            let input: PathBuf = match path::normalize(data.request.path()) {
+                Some(path) => From::from(&*path),
+                None => return Box::new(::futures::future::ok(data)),
+            };
+            let mut output: PathBuf = PathBuf::new();
+
+            // Remove bad path components (all component except normal ones)
+            for component in input.components() {
+                if let Component::Normal(osstr) = component {
+                    output.push(osstr);
+                }
+            }
+
+            output
+        };
+
+        // The above work was not dependent on blocking calls. However, reading the file
+        // is, and so we do that work within the threadpool
+        let pool = data.shared.pool.clone();
+        let settings = self.settings.clone();
+        Box::new(
+            pool.clone().spawn_fn(move|| {
+
+                if settings.source.metadata(&filepath).is_ok_and(|m| m.is_dir) {
+                    if !settings.permits(&filepath) {
+                        return Ok(data);
+                    }
+                    let index = settings.index.as_ref().map(|index| filepath.join(index));
+                    match index {
+                        Some(index) if settings.source.metadata(&index).is_ok()
+                            || settings.autoindex.is_none() => {
+                            filepath = index;
+                        },
+                        _ => match settings.autoindex {
+                            Some(ref autoindex) => {
+                                return Ok(settings.list(data, &filepath, autoindex));
+                            },
+                            // Do not handle directory requests if index is None
+                            // (pass the data on to the next handler)
+                            None => match settings.fallback(&data.request) {
+                                Some(fallback) => filepath = fallback,
+                                None => return Ok(data),
+                            },
+                        },
+                    }
+                }
+
+                let opened = match settings.open(&filepath) {
+                    Err(ref e) if e.kind() == io::ErrorKind::NotFound => {
+                        match settings.fallback(&data.request) {
+                            Some(fallback) if fallback != filepath => {
+                                filepath = fallback;
+                                settings.open(&filepath)
+                            },
+                            _ => return Ok(data),
+                        }
+                    },
+                    opened => opened,
+                };
+                match opened {
+                    // Pass the data on to the next handler
+                    Err(ref e) if e.kind() == io::ErrorKind::NotFound => Ok(data),
+                    Err(e) => {
+                        // File exists, but we cannot open it for some reason
+                        warn!("Cannot open {:?}: {}", filepath, e);
+                        let mut data = data;
+                        data.response.set_status(StatusCode::InternalServerError);
+                        Ok(data)
+                    },
+                    Ok((content, metadata)) => {
+                        let sibling = if settings.precompressed {
+                            precompressed::find(&filepath, data.request.headers(),
+                                                |path| settings.open(path))
+                        } else {
+                            None
+                        };
+                        Ok(settings.serve(data, &filepath, content, &metadata, sibling, &pool))
+                    }
+                }
+            })
+        )
+    }
+}
+
+impl Settings {
+    /// Whether a file (or directory) may be served
+    fn permits(&self, path: &Path) -> bool {
+        self.access.permits(path) && self.source.links_permitted(path, self.access.symlinks)
+    }
+
+    /// The file to serve for a request which matched none, if any
+    fn fallback(&self, request: &Request) -> Option<PathBuf> {
+        self.fallback.as_ref()
+            .filter(|fallback| fallback.applies(request))
+            .map(Fallback::path)
+    }
+
+    /// Open a file, through the cache if there is one.  Files which are refused
+    /// are not found.
+    fn open(&self, path: &Path) -> io::Result<(Content, Metadata)> {
+        if !self.permits(path) {
+            return Err(io::Error::new(io::ErrorKind::NotFound, "Access refused"));
+        }
+        match self.cache {
+            Some(ref cache) => cache.open(&*self.source, path),
+            None => self.source.open(path),
+        }
+    }
+
+    /// Respond with a listing of a directory
+    fn list<S>(&self, mut data: PluginData<S>, dir: &Path, autoindex: &Autoindex)
+               -> PluginData<S>
+    {
+        // Listings link relative to the directory, so its path must end in a slash
+        let url_path = match path::normalize(data.request.path()) {
+            Some(url_path) => url_path.into_owned(),
+            None => return data,
+        };
+        if !url_path.ends_with('/') {
+            let location = match data.request.query() {
+                Some(query) => format!("{}/?{}", path::encode(&url_path), query),
+                None => format!("{}/", path::encode(&url_path)),
+            };
+            data.redirect(StatusCode::MovedPermanently, &location);
+            return data;
+        }
+
+        let entries = self.source.read_dir(dir).map(|entries| {
+            entries.into_iter().filter(|(name, _)| self.permits(&dir.join(name))).collect()
+        });
+        match entries {
+            Ok(entries) => {
+                let (content_type, body) = autoindex.render(entries, &url_path,
+                                                            data.request.headers());
+                let head = data.request.method() == &Method::Head;
+                data.response.set_status(StatusCode::Ok);
+                data.response.headers_mut().set(ContentType(content_type));
+                data.response.headers_mut().set(ContentLength(body.len() as u64));
+                if !head {
+                    data.response.set_body(body);
+                }
+            },
+            Err(e) => {
+                warn!("Cannot list {:?}: {}", dir, e);
+                data.response.set_status(StatusCode::InternalServerError);
+            },
+        }
+        data
+    }
+
+    /// Respond with an open file, or its precompressed sibling
+    fn serve<S>(&self, mut data: PluginData<S>, filepath: &Path, content: Content,
+                metadata: &Metadata, sibling: Option<Sibling>, pool: &CpuPool)
+                -> PluginData<S>
+    {
+        if self.precompressed {
+            precompressed::vary(data.response.headers_mut());
+        }
+        let (content, metadata, validators) = match sibling {
+            Some(sibling) => {
+                let mut validators = Validators::new(&sibling.metadata, self.etags);
+                // The tag must differ from that of the uncompressed file
+                if let Some(ref mut etag) = validators.etag {
+                    let tag = format!("{}-{}", etag.tag(), sibling.extension);
+                    etag.set_tag(tag);
+                }
+                data.response.headers_mut().set(ContentEncoding(vec![sibling.encoding]));
+                (sibling.content, sibling.metadata, validators)
+            },
+            None => (content, metadata.clone(), Validators::new(metadata, self.etags)),
+        };
+        let metadata = &metadata;
+
+        validators.set_headers(data.response.headers_mut());
+        if let Some(status) = validators.evaluate(data.request.headers()) {
+            data.response.set_status(status);
+            return data;
+        }
+
+        let len = metadata.len;
+        let content_type = self.mime_types.get(filepath);
+        let head = data.request.method() == &Method::Head;
+        let headers = data.response.headers_mut();
+        headers.set(AcceptRanges(vec![RangeUnit::Bytes]));
+
+        // The file is streamed in chunks, rather than read into memory at once
+        match Ranges::new(data.request.headers(), len, &validators) {
+            Ranges::Full => {
+                headers.set(ContentLength(len));
+                if let Some(mime) = content_type {
+                    headers.set(ContentType(mime.clone()));
+                }
+                data.response.set_status(StatusCode::Ok);
+                if !head {
+                    data.response.set_body(body::from_reader(pool, content));
+                }
+            },
+            Ranges::Unsatisfiable => {
+                headers.set(ContentRange(ranges::content_range(None, len)));
+                headers.set(ContentLength(0));
+                data.response.set_status(StatusCode::RangeNotSatisfiable);
+            },
+            Ranges::Partial(ref ranges) if ranges.len() == 1 => {
+                let (first, last) = ranges[0];
+                headers.set(ContentRange(ranges::content_range(Some((first, last)), len)));
+                headers.set(ContentLength(last - first + 1));
+                if let Some(mime) = content_type {
+                    headers.set(ContentType(mime.clone()));
+                }
+                data.response.set_status(StatusCode::PartialContent);
+                if !head {
+                    let mut content = content;
+                    match content.seek(SeekFrom::Start(first)) {
+                        Ok(_) => {
+                            let reader = content.take(last - first + 1);
+                            data.response.set_body(body::from_reader(pool, reader));
+                        },
+                        Err(e) => {
+                            warn!("Cannot seek in {:?}: {}", filepath, e);
+                            data.response.set_status(StatusCode::InternalServerError);
+                        },
+                    }
+                }
+            },
+            Ranges::Partial(ref ranges) => {
+                let multipart = ranges::Multipart::new(ranges, len, content_type);
+                headers.set(ContentLength(multipart.len()));
+                headers.set(ContentType(multipart.content_type()));
+                data.response.set_status(StatusCode::PartialContent);
+                if !head {
+                    data.response.set_body(body::from_reader(pool, multipart.reader(content)));
+                }
+            },
+        }
+        data
+    }
+}
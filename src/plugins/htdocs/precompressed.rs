@@ -0,0 +1,83 @@
+use std::cmp::Reverse;
+use std::ffi::OsString;
+use std::io;
+use std::path::{Path, PathBuf};
+use hyper::Headers;
+use hyper::header::{AcceptEncoding, Encoding, q};
+use super::source::{Content, Metadata};
+
+/// The encodings looked for, in order of preference, with the extension of the
+/// files which hold them
+const ENCODINGS: &[(&str, Encoding)] = &[
+    ("br", Encoding::Brotli),
+    ("gz", Encoding::Gzip),
+];
+
+/// A precompressed version of a file
+pub struct Sibling {
+    pub content: Content,
+    pub metadata: Metadata,
+    pub encoding: Encoding,
+    /// The extension of the sibling, such as `gz`
+    pub extension: &'static str,
+}
+
+/// Find the best precompressed sibling of a file (e.g. `app.js.br` for
+/// `app.js`) which the client accepts, by its `Accept-Encoding` header.
+///
+/// Encodings the client rates more highly are preferred; between equally rated
+/// encodings, Brotli is preferred.
+pub fn find<F>(path: &Path, headers: &Headers, open: F) -> Option<Sibling>
+    where F: Fn(&Path) -> io::Result<(Content, Metadata)>
+{
+    let accept = headers.get::<AcceptEncoding>()?;
+    // An encoding listed by name is rated by that entry, not by `*`
+    let quality = |encoding: &Encoding| {
+        let rated = |wanted: &Encoding| accept.iter()
+            .filter(|item| item.item == *wanted)
+            .map(|item| item.quality)
+            .max();
+        rated(encoding)
+            .or_else(|| rated(&Encoding::EncodingExt("*".to_owned())))
+            .unwrap_or(q(0))
+    };
+
+    let mut candidates: Vec<(&'static str, Encoding)> = ENCODINGS.iter()
+        .filter(|(_, encoding)| quality(encoding) > q(0))
+        .map(|(extension, encoding)| (*extension, encoding.clone()))
+        .collect();
+    // A stable sort keeps our preference between equally rated encodings
+    candidates.sort_by_key(|candidate| Reverse(quality(&candidate.1)));
+
+    for (extension, encoding) in candidates {
+        let mut name = OsString::from(path.as_os_str());
+        name.push(".");
+        name.push(extension);
+        if let Ok((content, metadata)) = open(&PathBuf::from(name)) {
+            if !metadata.is_dir {
+                return Some(Sibling { content, metadata, encoding, extension });
+            }
+        }
+    }
+    None
+}
+
+/// Add `Accept-Encoding` to the `Vary` header, keeping the request headers it
+/// already lists (such as `Origin`)
+pub fn vary(headers: &mut Headers) {
+    let mut vary: Vec<String> = match headers.get_raw("Vary") {
+        Some(raw) => raw.iter()
+            .filter_map(|line| ::std::str::from_utf8(line).ok())
+            .flat_map(|line| line.split(','))
+            .map(|name| name.trim())
+            .filter(|name| !name.is_empty())
+            .map(|name| name.to_owned())
+            .collect(),
+        None => Vec::new(),
+    };
+    if vary.iter().any(|name| name == "*" || name.eq_ignore_ascii_case("Accept-Encoding")) {
+        return;
+    }
+    vary.push("Accept-Encoding".to_owned());
+    headers.set_raw("Vary", vary.join(", "));
+}
@@ -0,0 +1,189 @@
+use std::cmp;
+use std::io::{self, Read, Seek, SeekFrom};
+use hyper::Headers;
+use hyper::header::{ByteRangeSpec, ContentRangeSpec, IfRange, Range};
+use hyper::mime::Mime;
+use textnonce::TextNonce;
+use crate::plugins::htdocs::conditional::Validators;
+
+/// The most ranges served in one response, after overlapping ranges are merged.
+/// Requests for more are served the whole file.
+const MAX_RANGES: usize = 32;
+
+/// The part of a file to send
+pub enum Ranges {
+    /// The whole file
+    Full,
+    /// These byte ranges (first and last byte, inclusive), in ascending order
+    Partial(Vec<(u64, u64)>),
+    /// The requested ranges are outside the file
+    Unsatisfiable,
+}
+
+impl Ranges {
+    /// Decide which ranges of a file of length `len` to send, per RFC 7233.
+    ///
+    /// The `Range` header is ignored unless it is for bytes, and unless any
+    /// `If-Range` header matches the file's validators: its entity tag by strong
+    /// comparison, or its modification time exactly.
+    pub fn new(headers: &Headers, len: u64, validators: &Validators) -> Ranges {
+        let specs = match headers.get::<Range>() {
+            Some(Range::Bytes(specs)) => specs,
+            _ => return Ranges::Full,
+        };
+
+        let if_range_matches = match headers.get::<IfRange>() {
+            Some(IfRange::EntityTag(tag)) => {
+                validators.etag.as_ref().is_some_and(|etag| etag.strong_eq(tag))
+            },
+            Some(IfRange::Date(date)) => validators.last_modified == Some((*date).into()),
+            None => true,
+        };
+        if !if_range_matches {
+            return Ranges::Full;
+        }
+
+        let mut ranges: Vec<(u64, u64)> = specs.iter()
+            .filter_map(|spec: &ByteRangeSpec| spec.to_satisfiable_range(len))
+            .collect();
+        if ranges.is_empty() {
+            return Ranges::Unsatisfiable;
+        }
+
+        // Merge overlapping and adjacent ranges, so that a client cannot make us
+        // send the same bytes many times over
+        ranges.sort();
+        let mut merged: Vec<(u64, u64)> = Vec::with_capacity(ranges.len());
+        for (first, last) in ranges {
+            match merged.last_mut() {
+                Some(previous) if first <= previous.1 + 1 => {
+                    previous.1 = cmp::max(previous.1, last);
+                },
+                _ => merged.push((first, last)),
+            }
+        }
+        if merged.len() > MAX_RANGES {
+            return Ranges::Full;
+        }
+        Ranges::Partial(merged)
+    }
+}
+
+/// The `Content-Range` of a range of a file of length `len`
+pub fn content_range(range: Option<(u64, u64)>, len: u64) -> ContentRangeSpec {
+    ContentRangeSpec::Bytes {
+        range,
+        instance_length: Some(len),
+    }
+}
+
+/// A `multipart/byteranges` body, holding several ranges of a file
+pub struct Multipart {
+    boundary: String,
+    pieces: Vec<Piece>,
+}
+
+enum Piece {
+    Text(Vec<u8>),
+    /// First byte and length
+    Range(u64, u64),
+}
+
+impl Multipart {
+    /// Lay out the body for some ranges of a file of length `len`
+    pub fn new(ranges: &[(u64, u64)], len: u64, content_type: Option<&Mime>) -> Multipart {
+        let boundary: String = TextNonce::sized_urlsafe(32).unwrap().into_string()
+            .chars().filter(|c| c.is_ascii_alphanumeric()).collect();
+
+        let mut pieces = Vec::with_capacity(ranges.len() * 2 + 1);
+        for &(first, last) in ranges {
+            let mut header = format!("\r\n--{}\r\n", boundary);
+            if let Some(content_type) = content_type {
+                header.push_str(&format!("Content-Type: {}\r\n", content_type));
+            }
+            header.push_str(&format!("Content-Range: {}\r\n\r\n",
+                                     content_range(Some((first, last)), len)));
+            pieces.push(Piece::Text(header.into_bytes()));
+            pieces.push(Piece::Range(first, last - first + 1));
+        }
+        pieces.push(Piece::Text(format!("\r\n--{}--\r\n", boundary).into_bytes()));
+
+        Multipart { boundary, pieces }
+    }
+
+    /// The `Content-Type` of the body
+    pub fn content_type(&self) -> Mime {
+        format!("multipart/byteranges; boundary={}", self.boundary).parse().unwrap()
+    }
+
+    /// The length of the body
+    pub fn len(&self) -> u64 {
+        self.pieces.iter().map(|piece| match *piece {
+            Piece::Text(ref text) => text.len() as u64,
+            Piece::Range(_, len) => len,
+        }).sum()
+    }
+
+    /// A reader for the body, reading the ranges from a file
+    pub fn reader<R: Read + Seek>(self, file: R) -> MultipartReader<R> {
+        MultipartReader {
+            file,
+            pieces: self.pieces.into_iter().rev().collect(),
+            offset: 0,
+        }
+    }
+}
+
+/// Reads a `multipart/byteranges` body
+pub struct MultipartReader<R> {
+    file: R,
+    /// The pieces still to read, last first
+    pieces: Vec<Piece>,
+    /// How far into the current piece has been read
+    offset: u64,
+}
+
+impl<R: Read + Seek> Read for MultipartReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        if buf.is_empty() {
+            return Ok(0);
+        }
+        loop {
+            let read = match self.pieces.last() {
+                None => return Ok(0),
+                Some(Piece::Text(text)) => {
+                    let rest = &text[self.offset as usize..];
+                    let len = cmp::min(rest.len(), buf.len());
+                    buf[..len].copy_from_slice(&rest[..len]);
+                    len
+                },
+                Some(&Piece::Range(first, len)) => {
+                    if self.offset == 0 {
+                        self.file.seek(SeekFrom::Start(first))?;
+                    }
+                    let want = cmp::min(len - self.offset, buf.len() as u64) as usize;
+                    let read = self.file.read(&mut buf[..want])?;
+                    if read == 0 && want > 0 {
+                        return Err(io::Error::new(io::ErrorKind::UnexpectedEof,
+                                                  "File shrank while being served"));
+                    }
+                    read
+                },
+            };
+            self.offset += read as u64;
+
+            let done = match self.pieces.last() {
+                Some(Piece::Text(text)) => self.offset == text.len() as u64,
+                Some(&Piece::Range(_, len)) => self.offset == len,
+                None => true,
+            };
+            if done {
+                self.pieces.pop();
+                self.offset = 0;
+            }
+            if read > 0 {
+                return Ok(read);
+            }
+        }
+    }
+}
@@ -0,0 +1,269 @@
+use std::collections::BTreeMap;
+use std::fs::{self, File};
+use std::io::{self, Cursor, Read, Seek, SeekFrom};
+use std::path::{Component, Path, PathBuf};
+use std::sync::Arc;
+use std::time::SystemTime;
+use super::access::Symlinks;
+
+/// Where Htdocs finds the files it serves, such as a directory on disk
+/// (`Filesystem`) or files compiled into the program (`Memory`).
+///
+/// Paths are relative to the root of the source, and hold only normal
+/// components (no root, `.` or `..`).  The methods block, and are called on the
+/// thread pool.
+pub trait Source: Send + Sync + 'static {
+    /// The metadata of a file or directory
+    fn metadata(&self, path: &Path) -> io::Result<Metadata>;
+
+    /// Open a file to read it
+    fn open(&self, path: &Path) -> io::Result<(Content, Metadata)>;
+
+    /// The names and metadata of the entries in a directory
+    fn read_dir(&self, path: &Path) -> io::Result<Vec<(String, Metadata)>>;
+
+    /// Whether the links (if any) a path is reached through are permitted by a
+    /// policy.  Sources without links permit every path.
+    fn links_permitted(&self, _path: &Path, _symlinks: Symlinks) -> bool {
+        true
+    }
+}
+
+/// The metadata of a file or directory in a `Source`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Metadata {
+    pub is_dir: bool,
+    pub len: u64,
+    pub modified: Option<SystemTime>,
+}
+
+impl<'a> From<&'a fs::Metadata> for Metadata {
+    fn from(metadata: &'a fs::Metadata) -> Metadata {
+        Metadata {
+            is_dir: metadata.is_dir(),
+            len: metadata.len(),
+            modified: metadata.modified().ok(),
+        }
+    }
+}
+
+/// The content of a file being served, either open on disk or in memory
+pub enum Content {
+    File(File),
+    Memory(Cursor<Arc<[u8]>>),
+}
+
+impl Read for Content {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        match *self {
+            Content::File(ref mut file) => file.read(buf),
+            Content::Memory(ref mut cursor) => cursor.read(buf),
+        }
+    }
+}
+
+impl Seek for Content {
+    fn seek(&mut self, pos: SeekFrom) -> io::Result<u64> {
+        match *self {
+            Content::File(ref mut file) => file.seek(pos),
+            Content::Memory(ref mut cursor) => cursor.seek(pos),
+        }
+    }
+}
+
+/// Files in a directory on disk
+pub struct Filesystem {
+    root: PathBuf,
+}
+
+impl Filesystem {
+    pub fn new<P>(root: P) -> Filesystem
+        where PathBuf: From<P>
+    {
+        Filesystem {
+            root: From::from(root),
+        }
+    }
+}
+
+impl Source for Filesystem {
+    fn metadata(&self, path: &Path) -> io::Result<Metadata> {
+        fs::metadata(self.root.join(path)).map(|metadata| Metadata::from(&metadata))
+    }
+
+    fn open(&self, path: &Path) -> io::Result<(Content, Metadata)> {
+        let file = File::open(self.root.join(path))?;
+        let metadata = file.metadata()?;
+        Ok((Content::File(file), Metadata::from(&metadata)))
+    }
+
+    fn read_dir(&self, path: &Path) -> io::Result<Vec<(String, Metadata)>> {
+        let mut entries = Vec::new();
+        for entry in fs::read_dir(self.root.join(path))? {
+            let entry = entry?;
+            // Names which are not UTF-8 could not be linked to reliably
+            let name = match entry.file_name().into_string() {
+                Ok(name) => name,
+                Err(_) => continue,
+            };
+            if let Ok(metadata) = fs::metadata(entry.path()) {
+                entries.push((name, Metadata::from(&metadata)));
+            }
+        }
+        Ok(entries)
+    }
+
+    fn links_permitted(&self, path: &Path, symlinks: Symlinks) -> bool {
+        match symlinks {
+            Symlinks::Follow => true,
+            Symlinks::Deny => {
+                let mut current = self.root.clone();
+                for component in path.components() {
+                    current.push(component);
+                    match fs::symlink_metadata(&current) {
+                        Ok(metadata) if metadata.file_type().is_symlink() => return false,
+                        Ok(_) => { },
+                        // Whatever is missing cannot be served anyway
+                        Err(_) => return true,
+                    }
+                }
+                true
+            },
+            Symlinks::WithinDocroot => {
+                let target = match fs::canonicalize(self.root.join(path)) {
+                    Ok(target) => target,
+                    Err(e) => return e.kind() == io::ErrorKind::NotFound,
+                };
+                match fs::canonicalize(&self.root) {
+                    Ok(root) => target.starts_with(root),
+                    Err(_) => false,
+                }
+            },
+        }
+    }
+}
+
+/// Files held in memory, such as assets compiled into the program with
+/// `include_bytes!`.
+///
+/// Directories are implied by the paths of the files in them.  Every file is
+/// given the time the source was created as its modification time, so that
+/// validators change when the program is restarted with new files.
+///
+/// ```ignore
+/// static ASSETS: &[(&str, &[u8])] = &[
+///     ("index.html", include_bytes!("../static/index.html")),
+///     ("css/site.css", include_bytes!("../static/css/site.css")),
+/// ];
+///
+/// let htdocs = Htdocs::with_source(Memory::from_manifest(ASSETS),
+///                                  Some("index.html".to_owned()));
+/// ```
+#[derive(Debug, Clone)]
+pub struct Memory {
+    files: BTreeMap<PathBuf, Arc<[u8]>>,
+    modified: SystemTime,
+}
+
+impl Default for Memory {
+    fn default() -> Memory {
+        Memory::new()
+    }
+}
+
+impl Memory {
+    /// An empty source
+    pub fn new() -> Memory {
+        Memory {
+            files: BTreeMap::new(),
+            modified: SystemTime::now(),
+        }
+    }
+
+    /// A source holding the files of a manifest of paths and contents
+    pub fn from_manifest(manifest: &[(&str, &[u8])]) -> Memory {
+        let mut memory = Memory::new();
+        for &(path, bytes) in manifest {
+            memory.insert(path, bytes);
+        }
+        memory
+    }
+
+    /// Add a file, such as `css/site.css`, replacing any at the same path
+    pub fn insert<B>(&mut self, path: &str, bytes: B)
+        where B: Into<Arc<[u8]>>
+    {
+        let path: PathBuf = Path::new(path).components()
+            .filter(|component| matches!(component, Component::Normal(_)))
+            .collect();
+        self.files.insert(path, bytes.into());
+    }
+
+    fn file_metadata(&self, bytes: &[u8]) -> Metadata {
+        Metadata {
+            is_dir: false,
+            len: bytes.len() as u64,
+            modified: Some(self.modified),
+        }
+    }
+
+    fn dir_metadata(&self) -> Metadata {
+        Metadata {
+            is_dir: true,
+            len: 0,
+            modified: Some(self.modified),
+        }
+    }
+
+    /// The paths of the files under a directory, in order
+    fn under<'a>(&'a self, dir: &'a Path) -> impl Iterator<Item = (&'a PathBuf, &'a Arc<[u8]>)> {
+        // Paths are ordered by component, so those under a directory follow it
+        self.files.range(dir.to_owned()..)
+            .skip_while(move |&(path, _)| path == dir)
+            .take_while(move |&(path, _)| path.starts_with(dir))
+    }
+}
+
+fn not_found() -> io::Error {
+    io::Error::new(io::ErrorKind::NotFound, "No such file")
+}
+
+impl Source for Memory {
+    fn metadata(&self, path: &Path) -> io::Result<Metadata> {
+        if let Some(bytes) = self.files.get(path) {
+            return Ok(self.file_metadata(bytes));
+        }
+        if self.under(path).next().is_some() {
+            return Ok(self.dir_metadata());
+        }
+        Err(not_found())
+    }
+
+    fn open(&self, path: &Path) -> io::Result<(Content, Metadata)> {
+        let bytes = self.files.get(path).ok_or_else(not_found)?;
+        Ok((Content::Memory(Cursor::new(bytes.clone())), self.file_metadata(bytes)))
+    }
+
+    fn read_dir(&self, path: &Path) -> io::Result<Vec<(String, Metadata)>> {
+        let mut entries: Vec<(String, Metadata)> = Vec::new();
+        for (file, bytes) in self.under(path) {
+            let mut rest = file.strip_prefix(path).unwrap_or(file).components();
+            let name = match rest.next() {
+                Some(name) => name.as_os_str().to_string_lossy().into_owned(),
+                None => continue,
+            };
+            if entries.last().is_some_and(|last| last.0 == name) {
+                continue;
+            }
+            let metadata = match rest.next() {
+                Some(_) => self.dir_metadata(),
+                None => self.file_metadata(bytes),
+            };
+            entries.push((name, metadata));
+        }
+        if entries.is_empty() && !path.as_os_str().is_empty() {
+            return Err(not_found());
+        }
+        Ok(entries)
+    }
+}
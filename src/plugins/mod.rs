@@ -1,9 +1,15 @@
 
+use std::collections::HashMap;
 use std::sync::Arc;
 use std::ops::Deref;
 use futures::Future;
 use hyper::server::{Request, Response};
-use crate::Shared;
+use hyper::StatusCode;
+use hyper::header::ContentType;
+use hyper::mime;
+use crate::{HttpError, Query, Shared};
+use crate::forms::{self, Limits, Multipart};
+use crate::plugins::router::{Urls, UrlError};
 
 pub struct PluginData<S>
 {
@@ -11,6 +17,166 @@ pub struct PluginData<S>
     pub request: Request,
     pub response: Response,
     pub session_id: Option<String>,
+    /// Parameters captured from the path by the `Router`
+    pub params: HashMap<String, String>,
+    /// The named routes of the `Router` which routed this request, if it has any
+    pub urls: Option<Arc<Urls>>,
+    /// The (normalized) path prefix which `Router::mount` has stripped from the
+    /// request URI, or an empty string outside of mounted routers
+    pub mount_prefix: String,
+}
+
+impl<S> PluginData<S>
+{
+    /// Create the data for a new request.  The response starts out as a 404 Not
+    /// Found, which plugins are expected to replace.
+    pub fn new(shared: Arc<Shared<S>>, request: Request) -> PluginData<S> {
+        PluginData {
+            shared,
+            request,
+            response: Response::new().with_status(StatusCode::NotFound),
+            session_id: None,
+            params: HashMap::new(),
+            urls: None,
+            mount_prefix: String::new(),
+        }
+    }
+
+    /// The parsed query string of the request.  It is parsed afresh on each call.
+    pub fn query(&self) -> Query {
+        Query::parse(self.request.query().unwrap_or("").as_bytes())
+    }
+
+    /// Read and parse the request body as an `application/x-www-form-urlencoded`
+    /// form.  The body is taken from the request.
+    ///
+    /// A request with another `Content-Type` is a 415 Unsupported Media Type
+    /// error, and one which exceeds the limits is a 413 Payload Too Large error.
+    pub fn form(&mut self, limits: &Limits) -> Box<dyn Future<Item = Query, Error = HttpError>>
+    {
+        let is_form = match self.request.headers().get::<ContentType>() {
+            Some(ContentType(mime)) => {
+                mime.type_() == mime::APPLICATION && mime.subtype() == mime::WWW_FORM_URLENCODED
+            },
+            None => false,
+        };
+        if !is_form {
+            return Box::new(::futures::future::err(HttpError::new(
+                StatusCode::UnsupportedMediaType, "Expected a URL-encoded form")));
+        }
+        let body = self.request.body_mut().take().unwrap_or_default();
+        forms::urlencoded(body, limits)
+    }
+
+    /// Stream the parts of a `multipart/form-data` request body.  The body is taken
+    /// from the request.
+    ///
+    /// A request with another `Content-Type` is a 415 Unsupported Media Type
+    /// error.
+    pub fn multipart(&mut self, limits: &Limits) -> Result<Multipart, HttpError> {
+        let boundary = match self.request.headers().get::<ContentType>() {
+            Some(ContentType(mime)) if mime.type_() == mime::MULTIPART
+                && mime.subtype() == mime::FORM_DATA =>
+            {
+                mime.get_param(mime::BOUNDARY).map(|b| b.as_str().to_owned())
+                    .ok_or_else(|| HttpError::bad_request("Multipart form has no boundary"))?
+            },
+            _ => return Err(HttpError::new(StatusCode::UnsupportedMediaType,
+                                           "Expected a multipart form")),
+        };
+        let body = self.request.body_mut().take().unwrap_or_default();
+        Ok(Multipart::new(body, &boundary, limits, &self.shared.pool))
+    }
+
+    /// Deserialize the query string of the request into `T`.  A query string which
+    /// does not fit `T` is a 400 Bad Request error.
+    #[cfg(feature = "serde")]
+    pub fn query_as<T>(&self) -> Result<T, HttpError>
+        where T: serde::de::DeserializeOwned
+    {
+        serde_urlencoded::from_str(self.request.query().unwrap_or(""))
+            .map_err(|e| HttpError::bad_request(&format!("Malformed query string: {}", e)))
+    }
+
+    /// Read the request body and deserialize it from JSON.  The body is taken from
+    /// the request, and must fit within `limits.max_total_size`.
+    ///
+    /// Errors have JSON bodies (see `HttpError::json`).  A request with a
+    /// `Content-Type` other than `application/json` is a 415 Unsupported Media
+    /// Type error, a body which is too large is a 413 Payload Too Large error,
+    /// malformed JSON is a 400 Bad Request error, and JSON which does not fit `T`
+    /// is a 422 Unprocessable Entity error.
+    #[cfg(feature = "json")]
+    pub fn json_body<T>(&mut self, limits: &Limits)
+                        -> Box<dyn Future<Item = T, Error = HttpError>>
+        where T: serde::de::DeserializeOwned + 'static
+    {
+        use futures::Stream;
+
+        let is_json = match self.request.headers().get::<ContentType>() {
+            Some(ContentType(mime)) => {
+                mime.type_() == mime::APPLICATION && (mime.subtype() == mime::JSON
+                                                      || mime.suffix() == Some(mime::JSON))
+            },
+            None => false,
+        };
+        if !is_json {
+            return Box::new(::futures::future::err(HttpError::json(
+                StatusCode::UnsupportedMediaType, "Expected a JSON body")));
+        }
+
+        let max_total_size = limits.max_total_size;
+        let body = self.request.body_mut().take().unwrap_or_default();
+        Box::new(
+            body.map_err(|e| HttpError::json(StatusCode::BadRequest,
+                                             &format!("Cannot read request body: {}", e)))
+                .fold(Vec::new(), move |mut body, chunk| {
+                    if (body.len() + chunk.len()) as u64 > max_total_size {
+                        return Err(HttpError::json(StatusCode::PayloadTooLarge,
+                                                   "Body is too large"));
+                    }
+                    body.extend_from_slice(&chunk);
+                    Ok(body)
+                })
+                .and_then(|body| {
+                    serde_json::from_slice(&body).map_err(|e| {
+                        let status = match e.classify() {
+                            serde_json::error::Category::Data => StatusCode::UnprocessableEntity,
+                            _ => StatusCode::BadRequest,
+                        };
+                        HttpError::json(status, &format!("Invalid JSON: {}", e))
+                    })
+                })
+        )
+    }
+
+    /// Respond with `value` serialized as JSON.
+    ///
+    /// If `value` cannot be serialized (e.g. it is a map with non-string keys),
+    /// this is logged, and the response is a 500 Internal Server Error.
+    #[cfg(feature = "json")]
+    pub fn respond_json<T>(&mut self, status: StatusCode, value: &T)
+        where T: serde::Serialize
+    {
+        let (status, body) = match serde_json::to_vec(value) {
+            Ok(body) => (status, body),
+            Err(e) => {
+                error!("Cannot serialize JSON response: {}", e);
+                let body = serde_json::json!({ "error": "Cannot serialize response" });
+                (StatusCode::InternalServerError, body.to_string().into_bytes())
+            }
+        };
+        self.respond(status, ContentType::json(), body);
+    }
+
+    /// Generate the path for a named route of the `Router` which routed this
+    /// request.  See `Router::url_for`.
+    pub fn url_for(&self, name: &str, params: &[(&str, &str)]) -> Result<String, UrlError> {
+        match self.urls {
+            Some(ref urls) => urls.url_for(name, params),
+            None => Err(UrlError::UnknownRoute(name.to_owned())),
+        }
+    }
 }
 
 /// A plugin provides a handler for a request.
@@ -40,8 +206,13 @@ impl<S,E,R,T> Plugin<S,E> for T
 }
 
 
+mod respond;
+
 pub mod router;
-pub use self::router::{Router, Handler};
+pub use self::router::{Router, Handler, IntoHandler};
+
+pub mod chain;
+pub use self::chain::Chain;
 
 pub mod page_visits;
 pub use self::page_visits::PageVisits;
@@ -54,3 +225,6 @@ pub use self::session::Session;
 
 pub mod good_citizen;
 pub use self::good_citizen::GoodCitizen;
+
+pub mod error_pages;
+pub use self::error_pages::ErrorPages;
@@ -10,13 +10,20 @@ pub struct PageVisits {
     counts: CHashMap<String, u32>,
 }
 
-impl PageVisits
+impl Default for PageVisits
 {
-    pub fn new()  -> PageVisits {
+    fn default() -> PageVisits {
         PageVisits {
             counts: CHashMap::new(),
         }
     }
+}
+
+impl PageVisits
+{
+    pub fn new()  -> PageVisits {
+        Default::default()
+    }
 
     /// This function gets the number of times the path was called.
     pub fn get(&self, url_path: &str) -> Option<u32>
@@ -0,0 +1,111 @@
+use std::fs::File;
+use std::io::{self, ErrorKind};
+use std::mem;
+use std::path::Path;
+use futures::Future;
+use hyper::StatusCode;
+use hyper::header::{ContentLength, ContentType, Location};
+use hyper::server::Response;
+use crate::{body, mime_types};
+use crate::plugins::PluginData;
+
+/// Helpers which set the whole response at once: its status, `Content-Type`,
+/// `Content-Length` and body.  Other headers already set on the response are kept.
+impl<S> PluginData<S>
+{
+    /// Respond 200 OK with an HTML page
+    pub fn html<B: Into<String>>(&mut self, body: B) {
+        self.respond(StatusCode::Ok, ContentType::html(), body.into().into_bytes());
+    }
+
+    /// Respond 200 OK with plain text
+    pub fn text<B: Into<String>>(&mut self, body: B) {
+        self.respond(StatusCode::Ok, ContentType::plaintext(), body.into().into_bytes());
+    }
+
+    /// Redirect to another location, with a 3xx status such as 303 See Other or
+    /// 308 Permanent Redirect.  The response has no body.
+    pub fn redirect(&mut self, status: StatusCode, location: &str) {
+        self.clear_body();
+        self.response.set_status(status);
+        self.response.headers_mut().remove::<ContentType>();
+        self.response.headers_mut().set(Location::new(location.to_owned()));
+        self.response.headers_mut().set(ContentLength(0));
+    }
+
+    /// Respond 404 Not Found, with a plain text explanation.  As this is a body,
+    /// an `ErrorPages` plugin does not replace it with its 404 page; to have that
+    /// page, set only the status.
+    pub fn not_found(&mut self) {
+        self.respond(StatusCode::NotFound, ContentType::plaintext(), b"Not Found".to_vec());
+    }
+
+    /// Respond 204 No Content
+    pub fn no_content(&mut self) {
+        self.clear_body();
+        self.response.set_status(StatusCode::NoContent);
+        self.response.headers_mut().remove::<ContentType>();
+        self.response.headers_mut().remove::<ContentLength>();
+    }
+
+    /// Respond 200 OK with the contents of a file, with a `Content-Type` guessed
+    /// from its extension (see `mime_types`).  The file is opened on the thread
+    /// pool, and streamed from there (see `body::from_reader`).
+    ///
+    /// If there is no such file, this responds with `not_found`.  If it cannot be
+    /// read, this is logged, and the response is a 500 Internal Server Error.
+    pub fn file<P, E>(self, path: P) -> Box<dyn Future<Item = PluginData<S>, Error = E>>
+        where P: AsRef<Path>,
+              S: Send + Sync + 'static,
+              E: Send + 'static
+    {
+        let path = path.as_ref().to_owned();
+        let pool = self.shared.pool.clone();
+        let mut data = self;
+        Box::new(
+            pool.clone().spawn_fn(move || {
+                let opened = File::open(&path).and_then(|f| {
+                    let metadata = f.metadata()?;
+                    if metadata.is_dir() {
+                        return Err(io::Error::new(ErrorKind::IsADirectory, "is a directory"));
+                    }
+                    Ok((f, metadata.len()))
+                });
+                match opened {
+                    Ok((f, len)) => {
+                        data.clear_body();
+                        data.response.set_status(StatusCode::Ok);
+                        data.response.headers_mut().set(ContentType(mime_types::guess(&path)));
+                        data.response.headers_mut().set(ContentLength(len));
+                        data.response.set_body(body::from_reader(&pool, f));
+                    },
+                    Err(ref e) if e.kind() == ErrorKind::NotFound
+                        || e.kind() == ErrorKind::IsADirectory => data.not_found(),
+                    Err(e) => {
+                        warn!("Cannot read {:?}: {}", path, e);
+                        data.clear_body();
+                        data.response.set_status(StatusCode::InternalServerError);
+                    }
+                }
+                Ok(data)
+            })
+        )
+    }
+
+    pub(crate) fn respond(&mut self, status: StatusCode, content_type: ContentType, body: Vec<u8>) {
+        self.response.set_status(status);
+        self.response.headers_mut().set(content_type);
+        self.response.headers_mut().set(ContentLength(body.len() as u64));
+        self.response.set_body(body);
+    }
+
+    /// Remove any body from the response, keeping its status and headers
+    fn clear_body(&mut self) {
+        if self.response.body_ref().is_some() {
+            let response = mem::replace(&mut self.response, Response::new());
+            self.response = Response::new()
+                .with_status(response.status())
+                .with_headers(response.headers().clone());
+        }
+    }
+}
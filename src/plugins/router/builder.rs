@@ -0,0 +1,98 @@
+use std::sync::Arc;
+use hyper::Method;
+use hyper::server::Request;
+use crate::plugins::router::{Router, Route, Handler, IntoHandler, Guard};
+use crate::plugins::router::pattern::Pattern;
+
+/// Wraps a handler as a route is defined, e.g. to run a group's plugins first
+type Wrap<'a, S, E> = Box<dyn FnOnce(Handler<S, E>) -> Handler<S, E> + 'a>;
+
+/// Defines a route step by step.  Create one with `Router::route`, and finish it
+/// with `to`.
+pub struct RouteBuilder<'a, S, E> {
+    router: &'a Router<S, E>,
+    pattern: Pattern,
+    method: Method,
+    name: Option<String>,
+    summary: Option<String>,
+    tags: Vec<String>,
+    guards: Vec<Guard>,
+    wrap: Option<Wrap<'a, S, E>>,
+}
+
+impl<'a, S, E> RouteBuilder<'a, S, E> {
+    pub(super) fn new(router: &'a Router<S, E>, pattern: Pattern, method: Method)
+                      -> RouteBuilder<'a, S, E>
+    {
+        RouteBuilder {
+            router,
+            pattern,
+            method,
+            name: None,
+            summary: None,
+            tags: Vec::new(),
+            guards: Vec::new(),
+            wrap: None,
+        }
+    }
+
+    /// Wrap the handler when the route is defined
+    pub(super) fn wrap<W>(mut self, wrap: W) -> RouteBuilder<'a, S, E>
+        where W: FnOnce(Handler<S, E>) -> Handler<S, E> + 'a
+    {
+        self.wrap = Some(Box::new(wrap));
+        self
+    }
+
+    /// Name the route, so that URLs can be generated for it.  See
+    /// `Router::insert_named`.
+    pub fn name(mut self, name: &str) -> RouteBuilder<'a, S, E> {
+        self.name = Some(name.to_owned());
+        self
+    }
+
+    /// Summarize what the route does, for documentation.  See `Router::routes`.
+    pub fn summary(mut self, summary: &str) -> RouteBuilder<'a, S, E> {
+        self.summary = Some(summary.to_owned());
+        self
+    }
+
+    /// Tag the route, for grouping it in documentation.  Routes may have several
+    /// tags.
+    pub fn tag(mut self, tag: &str) -> RouteBuilder<'a, S, E> {
+        self.tags.push(tag.to_owned());
+        self
+    }
+
+    /// Only use this route if the guard passes.  If a route has several guards,
+    /// they must all pass.  See the `guard` module.
+    pub fn guard<G>(mut self, guard: G) -> RouteBuilder<'a, S, E>
+        where G: Fn(&Request) -> bool + Send + Sync + 'static
+    {
+        self.guards.push(Arc::new(guard));
+        self
+    }
+
+    /// Define the route with this handler.
+    ///
+    /// A route without guards replaces any other route without guards that has
+    /// the same pattern and method.  Routes with guards are added alongside them.
+    pub fn to<H, M>(self, handler: H)
+        where H: IntoHandler<S, E, M>
+    {
+        let mut handler = handler.into_handler();
+        if let Some(wrap) = self.wrap {
+            handler = wrap(handler);
+        }
+        self.router.add(Route {
+            name: self.name,
+            summary: self.summary,
+            tags: self.tags,
+            pattern: self.pattern,
+            method: Some(self.method),
+            guards: self.guards,
+            handler,
+            mounted: None,
+        });
+    }
+}
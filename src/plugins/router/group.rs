@@ -0,0 +1,79 @@
+use std::sync::Arc;
+use futures::Future;
+use hyper::Method;
+use crate::plugins::{Chain, Plugin, PluginData};
+use crate::plugins::router::{Router, RouteBuilder, Handler, IntoHandler};
+use crate::plugins::router::pattern::Pattern;
+
+/// A group of routes which share a prefix and a list of plugins.  Create one
+/// with `Router::group`.
+pub struct Group<'a, S, E> {
+    router: &'a Router<S, E>,
+    prefix: Pattern,
+    plugins: Option<Arc<Chain<S, E>>>,
+}
+
+impl<'a, S, E> Group<'a, S, E>
+    where S: 'static,
+          E: 'static
+{
+    pub(super) fn new(router: &'a Router<S, E>, prefix: Pattern,
+                      plugins: Vec<Arc<Box<dyn Plugin<S, E> + Send + Sync>>>)
+                      -> Group<'a, S, E>
+    {
+        Group {
+            router,
+            prefix,
+            plugins: if plugins.is_empty() { None } else { Some(Arc::new(Chain::new(plugins))) },
+        }
+    }
+
+    /// Define a route under the group's prefix (insert or replace).  A path of `/`
+    /// routes the prefix itself.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the pattern is malformed, e.g. it has segments after a wildcard.
+    pub fn insert<H, M>(&self, path: &str, method: Method, handler: H)
+        where H: IntoHandler<S, E, M>
+    {
+        self.route(path, method).to(handler);
+    }
+
+    /// Define a named route under the group's prefix (insert or replace).  See
+    /// `Router::insert_named`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the pattern is malformed, e.g. it has segments after a wildcard.
+    pub fn insert_named<H, M>(&self, name: &str, path: &str, method: Method, handler: H)
+        where H: IntoHandler<S, E, M>
+    {
+        self.route(path, method).name(name).to(handler);
+    }
+
+    /// Start defining a route under the group's prefix.  See `Router::route`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the pattern is malformed, e.g. it has segments after a wildcard.
+    pub fn route(&self, path: &str, method: Method) -> RouteBuilder<'a, S, E> {
+        let plugins = self.plugins.clone();
+        RouteBuilder::new(self.router, Pattern::parse(path).prefixed(&self.prefix), method)
+            .wrap(move |handler| Group::wrap(plugins, handler))
+    }
+
+    /// Run the group's plugins before the handler
+    fn wrap(plugins: Option<Arc<Chain<S, E>>>, handler: Handler<S, E>) -> Handler<S, E> {
+        match plugins {
+            None => handler,
+            Some(plugins) => {
+                Arc::new(move |data: PluginData<S>| {
+                    let handler = handler.clone();
+                    Box::new(plugins.handle(data).and_then(move |data| handler(data)))
+                        as Box<dyn Future<Item = PluginData<S>, Error = E>>
+                })
+            },
+        }
+    }
+}
@@ -0,0 +1,66 @@
+//! Guards select between routes using properties of the request other than its
+//! path and method.  Any `Fn(&Request) -> bool` can be used as a guard; this module
+//! provides some common ones.
+
+use std::sync::Arc;
+use hyper::header::{q, Accept, Host};
+use hyper::mime::{Mime, STAR};
+use hyper::server::Request;
+
+/// A guard, as stored by the `Router`
+pub type Guard = Arc<dyn Fn(&Request) -> bool + Send + Sync>;
+
+/// Passes if the `Host` header names this host (ignoring case and any port)
+pub fn host(host: &str) -> impl Fn(&Request) -> bool + Send + Sync + 'static {
+    let host = host.to_owned();
+    move |request: &Request| {
+        match request.headers().get::<Host>() {
+            Some(h) => h.hostname().eq_ignore_ascii_case(&host),
+            None => false,
+        }
+    }
+}
+
+/// Passes if the client accepts this content type, according to its `Accept`
+/// header.  Clients which send no `Accept` header accept anything.
+///
+/// # Panics
+///
+/// Panics if `content_type` is not a valid media type.
+pub fn accept(content_type: &str) -> impl Fn(&Request) -> bool + Send + Sync + 'static {
+    let wanted: Mime = match content_type.parse() {
+        Ok(mime) => mime,
+        Err(_) => panic!("Invalid content type {:?}", content_type),
+    };
+    move |request: &Request| {
+        let accept = match request.headers().get::<Accept>() {
+            Some(accept) => accept,
+            None => return true,
+        };
+        accept.iter().any(|item| {
+            let range = &item.item;
+            item.quality > q(0)
+                && (range.type_() == STAR || range.type_() == wanted.type_())
+                && (range.subtype() == STAR || range.subtype() == wanted.subtype())
+        })
+    }
+}
+
+/// Passes if the request has this header, whatever its value
+pub fn header(name: &str) -> impl Fn(&Request) -> bool + Send + Sync + 'static {
+    let name = name.to_owned();
+    move |request: &Request| request.headers().get_raw(&name).is_some()
+}
+
+/// Passes if the request has this header with exactly this value.  This suits
+/// API version headers, e.g. `header_value("Api-Version", "2")`.
+pub fn header_value(name: &str, value: &str) -> impl Fn(&Request) -> bool + Send + Sync + 'static {
+    let name = name.to_owned();
+    let value = value.to_owned();
+    move |request: &Request| {
+        match request.headers().get_raw(&name).and_then(|raw| raw.one()) {
+            Some(v) => v == value.as_bytes(),
+            None => false,
+        }
+    }
+}
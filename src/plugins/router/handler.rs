@@ -0,0 +1,44 @@
+use std::sync::Arc;
+use futures::Future;
+use crate::plugins::{Plugin, PluginData};
+
+/// A route handler, as stored by the `Router`
+pub type Handler<S, E> = Arc<dyn Fn(PluginData<S>)
+                             -> Box<dyn Future<Item = PluginData<S>, Error = E>>
+                             + Send + Sync>;
+
+/// Marks handlers which are plain functions or closures
+pub struct FnHandler;
+
+/// Marks handlers which are plugins
+pub struct PluginHandler;
+
+/// Anything which can handle a route.
+///
+/// This is implemented for functions and closures taking a `PluginData`, and for
+/// any `Plugin` (such as a `Chain` or another `Router`) so that a whole sub-chain
+/// can be mounted on a single route.  Either way, the handler must be
+/// `Send + Sync + 'static`.
+///
+/// The `M` type parameter only serves to keep those two implementations apart;
+/// it is inferred.
+pub trait IntoHandler<S, E, M> {
+    fn into_handler(self) -> Handler<S, E>;
+}
+
+impl<S, E, F> IntoHandler<S, E, FnHandler> for F
+    where F: Fn(PluginData<S>) -> Box<dyn Future<Item = PluginData<S>, Error = E>>
+             + Send + Sync + 'static
+{
+    fn into_handler(self) -> Handler<S, E> {
+        Arc::new(self)
+    }
+}
+
+impl<S, E, P> IntoHandler<S, E, PluginHandler> for P
+    where P: Plugin<S, E> + Send + Sync + 'static
+{
+    fn into_handler(self) -> Handler<S, E> {
+        Arc::new(move |data| self.handle(data))
+    }
+}
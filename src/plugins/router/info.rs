@@ -0,0 +1,26 @@
+use hyper::Method;
+use crate::plugins::router::pattern::Pattern;
+
+/// A description of a route, as listed by `Router::routes`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RouteInfo {
+    pub method: Method,
+    /// The route pattern, such as `/users/:id`
+    pub pattern: String,
+    pub name: Option<String>,
+    /// A short summary of what the route does
+    pub summary: Option<String>,
+    /// Tags for grouping routes in documentation
+    pub tags: Vec<String>,
+    /// Whether the route has guards, so that it may not always be used
+    pub guarded: bool,
+    pub(crate) parsed: Pattern,
+}
+
+impl RouteInfo {
+    pub(crate) fn prefixed(mut self, prefix: &Pattern) -> RouteInfo {
+        self.parsed = self.parsed.prefixed(prefix);
+        self.pattern = self.parsed.to_string();
+        self
+    }
+}
@@ -0,0 +1,576 @@
+use std::sync::{Arc, Mutex};
+use arc_swap::ArcSwap;
+use futures::{Future, Stream};
+use hyper::{Method, StatusCode};
+use hyper::header::{Allow, ContentLength, Location};
+use hyper::server::{Request, Response};
+use crate::path::{self, TrailingSlash};
+use crate::plugins::{Plugin, PluginData};
+
+mod pattern;
+use self::pattern::{Pattern, Segment};
+
+mod trie;
+use self::trie::Node;
+
+mod handler;
+pub use self::handler::{Handler, IntoHandler, FnHandler, PluginHandler};
+
+mod urls;
+pub use self::urls::{Urls, UrlError};
+
+mod group;
+pub use self::group::Group;
+
+pub mod guard;
+pub use self::guard::Guard;
+
+mod builder;
+pub use self::builder::RouteBuilder;
+
+mod info;
+pub use self::info::RouteInfo;
+
+#[cfg(feature = "openapi")]
+pub mod openapi;
+
+struct Route<S, E> {
+    name: Option<String>,
+    summary: Option<String>,
+    tags: Vec<String>,
+    pattern: Pattern,
+    /// The method routed, or None for any method
+    method: Option<Method>,
+    /// Guards which must all pass for this route to be used
+    guards: Vec<Guard>,
+    handler: Handler<S, E>,
+    /// The router mounted on this route, if any
+    mounted: Option<Arc<Router<S, E>>>,
+}
+
+impl<S, E> Route<S, E> {
+    fn accepts(&self, method: &Method, request: &Request) -> bool {
+        let method_ok = match self.method {
+            Some(ref m) => m == method,
+            None => true,
+        };
+        method_ok && self.guards.iter().all(|guard| guard(request))
+    }
+
+    /// A copy of this route, without its name
+    fn unnamed(&self) -> Route<S, E> {
+        Route {
+            name: None,
+            summary: self.summary.clone(),
+            tags: self.tags.clone(),
+            pattern: self.pattern.clone(),
+            method: self.method.clone(),
+            guards: self.guards.clone(),
+            handler: self.handler.clone(),
+            mounted: self.mounted.clone(),
+        }
+    }
+
+    /// Describe this route, or the routes of the router mounted on it
+    fn info(&self, into: &mut Vec<RouteInfo>) {
+        match (&self.mounted, &self.method) {
+            (Some(mounted), _) => {
+                let segments = &self.pattern.segments;
+                let prefix = Pattern::from_segments(segments[..segments.len() - 1].to_vec());
+                into.extend(mounted.routes().into_iter().map(|info| info.prefixed(&prefix)));
+            },
+            (None, Some(method)) => {
+                into.push(RouteInfo {
+                    method: method.clone(),
+                    pattern: self.pattern.to_string(),
+                    name: self.name.clone(),
+                    summary: self.summary.clone(),
+                    tags: self.tags.clone(),
+                    guarded: !self.guards.is_empty(),
+                    parsed: self.pattern.clone(),
+                });
+            },
+            (None, None) => {},
+        }
+    }
+}
+
+/// The routes, compiled for matching requests and generating URLs
+struct Table<S, E> {
+    trie: Node<Arc<Route<S,E>>>,
+    urls: Arc<Urls>,
+}
+
+/// A plugin which dispatches requests to handlers based on the request path and
+/// method.
+///
+/// Routes are patterns made of `/`-separated segments:
+///
+/// * `about` matches that exact segment
+/// * `:id` matches any single non-empty segment, and captures it as `id`
+/// * `*rest` matches the remainder of the path (possibly empty), capturing it as
+///   `rest`.  It must be the last segment.
+/// * `**` (or a bare `*`) is an anonymous wildcard, for prefix routes such as
+///   `/static/**`
+///
+/// Captured values are available to the handler in `PluginData::params`.
+///
+/// When more than one route matches, static segments beat parameters, and
+/// parameters beat wildcards, comparing segment by segment from the left.
+///
+/// If the path matches a route, but not for the request method, the router
+/// responds with 405 Method Not Allowed and an `Allow` header listing the methods
+/// that are routed.  `OPTIONS` requests are answered the same way with 200 OK,
+/// unless an `OPTIONS` route has been defined.
+///
+/// `HEAD` requests for which no `HEAD` route has been defined are handled by the
+/// `GET` route, if there is one.  The body is stripped from the response, but the
+/// headers (including `Content-Length`) are kept.
+///
+/// Request paths are normalized before matching (see `path::normalize`), so
+/// percent-escapes are decoded and duplicate slashes collapsed.  Paths that cannot
+/// be decoded safely match no route.  Whether `/about/` matches a route for
+/// `/about` is decided by the trailing slash policy; see `set_trailing_slash`.
+///
+/// Routes may be named with `insert_named`, and URLs for them generated with
+/// `url_for`.  Handlers can do the same with `PluginData::url_for`.
+///
+/// Routes can also be selected by other properties of the request, such as its
+/// `Host` or `Accept` headers, by defining them with guards (see `route` and the
+/// `guard` module).  Candidate routes for the same pattern and method are tried in
+/// the order they were defined, until one's guards all pass.
+///
+/// Another router can be mounted under a prefix with `mount`, and routes which
+/// share a prefix and some plugins can be defined with `group`.
+///
+/// Routes are compiled into a trie which is replaced atomically whenever routes
+/// are inserted or removed, so matching a request takes no locks.  Changing
+/// routes rebuilds the whole trie, and is comparatively slow.
+pub struct Router<S, E> {
+    routes: Mutex<Vec<Arc<Route<S,E>>>>,
+    table: ArcSwap<Table<S,E>>,
+    trailing_slash: TrailingSlash,
+}
+
+impl<S,E> Default for Router<S,E> {
+    fn default() -> Router<S, E> {
+        Router {
+            routes: Mutex::new(Vec::new()),
+            table: ArcSwap::from_pointee(Table {
+                trie: Node::default(),
+                urls: Arc::new(Urls::default()),
+            }),
+            trailing_slash: TrailingSlash::default(),
+        }
+    }
+}
+
+impl<S,E> Router<S,E> {
+    pub fn new() -> Router<S, E> {
+        Default::default()
+    }
+
+    /// Set how paths which differ from a route only by a trailing slash are
+    /// treated.  The default is `TrailingSlash::Strict`.
+    pub fn set_trailing_slash(&mut self, policy: TrailingSlash) {
+        self.trailing_slash = policy;
+    }
+
+    /// Define a route (insert or replace).
+    ///
+    /// The handler may be a function, a closure, or any `Plugin`; see
+    /// `IntoHandler`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the pattern is malformed, e.g. it has segments after a wildcard.
+    pub fn insert<H, M>(&self, path: &str, method: Method, handler: H)
+        where H: IntoHandler<S, E, M>
+    {
+        self.route(path, method).to(handler);
+    }
+
+    /// Define a named route (insert or replace).  The name can be used to generate
+    /// URLs for the route with `url_for`.  If another route already has this name,
+    /// it loses it.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the pattern is malformed, e.g. it has segments after a wildcard.
+    pub fn insert_named<H, M>(&self, name: &str, path: &str, method: Method, handler: H)
+        where H: IntoHandler<S, E, M>
+    {
+        self.route(path, method).name(name).to(handler);
+    }
+
+    /// Start defining a route, which may then be named or given guards before its
+    /// handler is set with `RouteBuilder::to`.
+    ///
+    /// ```ignore
+    /// router.route("/users", Method::Get)
+    ///     .guard(guard::accept("application/json"))
+    ///     .to(users_json);
+    /// router.insert("/users", Method::Get, users_html);
+    /// ```
+    ///
+    /// # Panics
+    ///
+    /// Panics if the pattern is malformed, e.g. it has segments after a wildcard.
+    pub fn route(&self, path: &str, method: Method) -> RouteBuilder<'_, S, E> {
+        RouteBuilder::new(self, Pattern::parse(path), method)
+    }
+
+    /// Mount another router under a prefix (insert or replace), for requests of
+    /// any method.
+    ///
+    /// While the mounted router handles a request, the prefix is stripped from the
+    /// request URI, so that it (and its handlers) see `/users` rather than
+    /// `/api/users`.  The stripped prefix is kept in `PluginData::mount_prefix`, and
+    /// the URI is restored afterwards.  Parameters captured by the prefix are
+    /// passed along.
+    ///
+    /// Requests the mounted router does not handle are answered with 405 Method
+    /// Not Allowed if routes of this router match them for other methods.
+    ///
+    /// The mounted router's named routes become named routes of this router, with
+    /// the prefix added.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the prefix is malformed, or ends in a wildcard.
+    pub fn mount(&self, prefix: &str, router: Router<S, E>)
+        where S: 'static,
+              E: 'static
+    {
+        let mut segments = Pattern::parse_prefix(prefix).segments;
+        if let Some(&Segment::Wildcard(_)) = segments.last() {
+            panic!("Route prefix {:?} ends in a wildcard", prefix);
+        }
+        segments.push(Segment::Wildcard(None));
+        let pattern = Pattern::from_segments(segments);
+
+        let router = Arc::new(router);
+        let mounted = router.clone();
+        let mount_pattern = pattern.clone();
+        let handler: Handler<S, E> = Arc::new(move |mut data: PluginData<S>| {
+            let original = data.request.uri().clone();
+            let (prefix, stripped) = {
+                let path = match path::normalize(data.request.path()) {
+                    Some(path) => path,
+                    None => return Box::new(::futures::future::ok(data)),
+                };
+                let rest = mount_pattern.remainder(&path);
+                let prefix = path[..path.len() - rest.len()].trim_end_matches('/').to_owned();
+                let rest = path::encode(rest);
+                let stripped = match data.request.query() {
+                    Some(query) => format!("/{}?{}", rest, query),
+                    None => format!("/{}", rest),
+                };
+                (prefix, stripped)
+            };
+            match stripped.parse() {
+                Ok(uri) => data.request.set_uri(uri),
+                Err(_) => return Box::new(::futures::future::ok(data)),
+            }
+            let outer_prefix = data.mount_prefix.clone();
+            data.mount_prefix.push_str(&prefix);
+            Box::new(
+                mounted.dispatch(data, true).map(move |mut data| {
+                    data.request.set_uri(original);
+                    data.mount_prefix = outer_prefix;
+                    data
+                })
+            )
+        });
+
+        self.add(Route {
+            name: None,
+            summary: None,
+            tags: Vec::new(),
+            pattern,
+            method: None,
+            guards: Vec::new(),
+            handler,
+            mounted: Some(router),
+        });
+    }
+
+    /// Start a group of routes which share a prefix, and which all run the given
+    /// plugins (in order) before their handler.  The plugins only run for requests
+    /// that match a route of the group.
+    pub fn group(&self, prefix: &str, plugins: Vec<Arc<Box<dyn Plugin<S, E> + Send + Sync>>>)
+                 -> Group<'_, S, E>
+        where S: 'static,
+              E: 'static
+    {
+        Group::new(self, Pattern::parse_prefix(prefix), plugins)
+    }
+
+    fn add(&self, route: Route<S, E>) {
+        let route = Arc::new(route);
+        let mut routes = self.routes.lock().unwrap();
+        // A name belongs to the route it was given to most recently
+        if route.name.is_some() {
+            for other in routes.iter_mut() {
+                if other.name == route.name {
+                    *other = Arc::new(other.unnamed());
+                }
+            }
+        }
+        let replaces = |r: &Arc<Route<S,E>>| {
+            r.method == route.method && r.pattern == route.pattern
+                && r.guards.is_empty() && route.guards.is_empty()
+        };
+        match routes.iter().position(replaces) {
+            Some(i) => routes[i] = route,
+            None => routes.push(route),
+        }
+        self.publish(&routes);
+    }
+
+    /// Remove a route
+    pub fn remove(&self, path: &str, method: Method) {
+        let pattern = Pattern::parse(path);
+        let method = Some(method);
+        let mut routes = self.routes.lock().unwrap();
+        routes.retain(|r| !(r.method == method && r.pattern == pattern));
+        self.publish(&routes);
+    }
+
+    /// Remove all routes
+    pub fn clear(&self) {
+        let mut routes = self.routes.lock().unwrap();
+        routes.clear();
+        self.publish(&routes);
+    }
+
+    /// List the routes, in the order they were defined.  The routes of mounted
+    /// routers are included, with their prefix, in place of the mount.
+    pub fn routes(&self) -> Vec<RouteInfo> {
+        let routes = self.routes.lock().unwrap();
+        let mut info = Vec::new();
+        for route in routes.iter() {
+            route.info(&mut info);
+        }
+        info
+    }
+
+    /// Generate the path for a named route, percent-encoding the parameters.
+    ///
+    /// This fails if there is no such route, or if any of its parameters are
+    /// missing or cannot be used in a path (see `Urls::url_for`).  Unused
+    /// parameters are ignored.
+    pub fn url_for(&self, name: &str, params: &[(&str, &str)]) -> Result<String, UrlError> {
+        self.table.load().urls.url_for(name, params)
+    }
+
+    /// Compile the routes into a new table, and swap it in for the old one
+    fn publish(&self, routes: &[Arc<Route<S,E>>]) {
+        let mut trie = Node::default();
+        let mut urls = Urls::default();
+        for route in routes {
+            trie.insert(&route.pattern, route.clone());
+            if let Some(ref name) = route.name {
+                urls.insert(name.clone(), route.pattern.clone());
+            }
+            if let Some(ref mounted) = route.mounted {
+                let segments = &route.pattern.segments;
+                let prefix = Pattern::from_segments(segments[..segments.len() - 1].to_vec());
+                urls.extend_prefixed(&prefix, &mounted.table.load().urls);
+            }
+        }
+        self.table.store(Arc::new(Table {
+            trie,
+            urls: Arc::new(urls),
+        }));
+    }
+}
+
+impl<S,E> Plugin<S,E> for Router<S,E>
+    where S: 'static,
+          E: 'static
+{
+    fn handle(&self, data: PluginData<S>)
+              -> Box<dyn Future<Item = PluginData<S>, Error = E>>
+    {
+        self.dispatch(data, false)
+    }
+}
+
+impl<S,E> Router<S,E>
+    where S: 'static,
+          E: 'static
+{
+    /// Route a request.  If `inherit_urls` is set, named routes already provided
+    /// by an outer router are kept.
+    fn dispatch(&self, mut data: PluginData<S>, inherit_urls: bool)
+                -> Box<dyn Future<Item = PluginData<S>, Error = E>>
+    {
+        let table = self.table.load();
+        let trie = &table.trie;
+        let path = match path::normalize(data.request.path()) {
+            Some(path) => path,
+            None => return Box::new(::futures::future::ok(data)),
+        };
+
+        let mut found = find_route(trie, &data.request, &path)
+            .map(|(route, head_as_get)| (route, head_as_get, None));
+        if found.is_none() && self.trailing_slash != TrailingSlash::Strict {
+            if let Some(other) = path::toggle_trailing_slash(&path) {
+                found = find_route(trie, &data.request, &other)
+                    .map(|(route, head_as_get)| (route, head_as_get, Some(other)));
+            }
+        }
+
+        // Under the redirect policy, requests for any path other than the canonical
+        // one (such as `//about`, `/a/./about` or `/about/` for `/about`) are
+        // redirected to it
+        let canonical = match found {
+            Some((_, _, ref other)) if self.trailing_slash == TrailingSlash::Redirect => {
+                let canonical = other.as_deref().unwrap_or(&path);
+                if other.is_some() || path::encode(canonical) != data.request.path() {
+                    Some(canonical.to_owned())
+                } else {
+                    None
+                }
+            },
+            _ => None,
+        };
+
+        if let Some(canonical) = canonical {
+            // A mounted router sees only the rest of the path
+            let canonical = format!("{}{}", data.mount_prefix, canonical);
+            let location = match data.request.query() {
+                Some(query) => format!("{}?{}", path::encode(&canonical), query),
+                None => path::encode(&canonical).into_owned(),
+            };
+            data.response.set_status(StatusCode::PermanentRedirect);
+            data.response.headers_mut().set(Location::new(location));
+            data.response.headers_mut().set(ContentLength(0));
+            return Box::new(::futures::future::ok(data));
+        }
+
+        match found {
+            Some((route, head_as_get, other)) => {
+                route.pattern.captures(other.as_deref().unwrap_or(&path), &mut data.params);
+                let keep_urls = inherit_urls && data.urls.is_some();
+                if !keep_urls && !table.urls.is_empty() {
+                    data.urls = Some(table.urls.clone());
+                }
+                let path = path.into_owned();
+                let fut = (route.handler)(data);
+                if head_as_get {
+                    Box::new(fut.and_then(strip_body))
+                } else if route.mounted.is_some() {
+                    // A mounted router answers every method, so if it handled
+                    // nothing, routes here for other methods are still allowed
+                    let table = self.table.load_full();
+                    let trailing_slash = self.trailing_slash;
+                    Box::new(fut.map(move |mut data| {
+                        let unhandled = data.response.status() == StatusCode::NotFound
+                            && data.response.body_ref().is_none()
+                            && !data.response.headers().has::<Allow>();
+                        if unhandled {
+                            allow_methods(&table.trie, trailing_slash, &mut data, &path);
+                        }
+                        data
+                    }))
+                } else {
+                    fut
+                }
+            },
+            None => {
+                let path = path.into_owned();
+                allow_methods(trie, self.trailing_slash, &mut data, &path);
+                Box::new(::futures::future::ok(data))
+            }
+        }
+    }
+}
+
+/// Answer a request which no route handled, if routes for other methods match its
+/// path: with 200 OK for `OPTIONS`, and otherwise 405 Method Not Allowed, listing
+/// those methods in an `Allow` header.
+fn allow_methods<S, E>(trie: &Node<Arc<Route<S,E>>>, trailing_slash: TrailingSlash,
+                       data: &mut PluginData<S>, path: &str)
+{
+    let mut allowed: Vec<Method> = Vec::new();
+    let request = &data.request;
+    let mut paths = vec![path.to_owned()];
+    if trailing_slash != TrailingSlash::Strict {
+        paths.extend(path::toggle_trailing_slash(path));
+    }
+    for path in &paths {
+        trie.for_each_match(path, &mut |r: &Arc<Route<S,E>>| {
+            if !r.guards.iter().all(|guard| guard(request)) {
+                return;
+            }
+            if let Some(ref method) = r.method {
+                if !allowed.contains(method) {
+                    allowed.push(method.clone());
+                }
+                if method == &Method::Get && !allowed.contains(&Method::Head) {
+                    allowed.push(Method::Head);
+                }
+            }
+        });
+    }
+    if !allowed.is_empty() {
+        if !allowed.contains(&Method::Options) {
+            allowed.push(Method::Options);
+        }
+        if data.request.method() == &Method::Options {
+            data.response.set_status(StatusCode::Ok);
+            data.response.headers_mut().set(ContentLength(0));
+        } else {
+            data.response.set_status(StatusCode::MethodNotAllowed);
+        }
+        data.response.headers_mut().set(Allow(allowed));
+    }
+}
+
+/// Find the route for a request, given its normalized path.  Also returns whether
+/// a `HEAD` request is being answered by a `GET` route.
+fn find_route<'t, S, E>(trie: &'t Node<Arc<Route<S,E>>>, request: &Request, path: &str)
+                        -> Option<(&'t Arc<Route<S,E>>, bool)>
+{
+    let method = request.method();
+    match trie.find(path, &|r: &Arc<Route<S,E>>| r.accepts(method, request)) {
+        Some(route) => Some((route, false)),
+        None if method == &Method::Head => {
+            trie.find(path, &|r: &Arc<Route<S,E>>| r.accepts(&Method::Get, request))
+                .map(|route| (route, true))
+        },
+        None => None,
+    }
+}
+
+/// Strip the body from a response to a `HEAD` request, keeping its headers.  If
+/// the response does not already have a `Content-Length`, it is set from the body.
+fn strip_body<S, E>(mut data: PluginData<S>)
+                    -> Box<dyn Future<Item = PluginData<S>, Error = E>>
+    where S: 'static,
+          E: 'static
+{
+    if data.response.body_ref().is_none() {
+        return Box::new(::futures::future::ok(data));
+    }
+
+    if data.response.headers().has::<ContentLength>() {
+        data.response.set_body(Vec::new());
+        return Box::new(::futures::future::ok(data));
+    }
+
+    let response = ::std::mem::replace(&mut data.response, Response::new());
+    let status = response.status();
+    let headers = response.headers().clone();
+    Box::new(
+        response.body().concat2().then(move |body| {
+            data.response = Response::new().with_status(status).with_headers(headers);
+            if let Ok(body) = body {
+                data.response.headers_mut().set(ContentLength(body.len() as u64));
+            }
+            Ok(data)
+        })
+    )
+}
@@ -0,0 +1,139 @@
+//! Generates OpenAPI 3 documents from the routes of a `Router`.
+//!
+//! ```ignore
+//! let doc = OpenApi::new("My API", "1.0").handler(&router.routes());
+//! router.insert("/openapi.json", Method::Get, doc);
+//! ```
+//!
+//! OpenAPI has no way to describe a path that matches any number of segments.
+//! Named wildcards (`*rest`) are described as a single `{rest}` parameter, and
+//! routes with anonymous wildcards (`**`) are left out.  Only one operation can be
+//! described for each path and method, so of several guarded routes, only the
+//! first is described.
+
+use futures::Future;
+use hyper::header::{ContentLength, ContentType};
+use hyper::StatusCode;
+use serde_json::{json, Map, Value};
+use crate::plugins::PluginData;
+use crate::plugins::router::RouteInfo;
+use crate::plugins::router::pattern::Segment;
+
+/// Describes an API, for generating its OpenAPI document
+pub struct OpenApi {
+    title: String,
+    version: String,
+    description: Option<String>,
+}
+
+impl OpenApi {
+    /// `title` and `version` describe the API itself (not the OpenAPI version)
+    pub fn new(title: &str, version: &str) -> OpenApi {
+        OpenApi {
+            title: title.to_owned(),
+            version: version.to_owned(),
+            description: None,
+        }
+    }
+
+    pub fn set_description(&mut self, description: &str) {
+        self.description = Some(description.to_owned());
+    }
+
+    /// Generate the OpenAPI document for these routes
+    pub fn document(&self, routes: &[RouteInfo]) -> Value {
+        let mut info = json!({
+            "title": self.title,
+            "version": self.version,
+        });
+        if let Some(ref description) = self.description {
+            info["description"] = json!(description);
+        }
+
+        let mut paths = Map::new();
+        for route in routes {
+            let (path, params) = match openapi_path(route) {
+                Some(p) => p,
+                None => continue,
+            };
+            let operations = paths.entry(path).or_insert_with(|| json!({}));
+            let method = route.method.as_ref().to_lowercase();
+            if operations.get(&method).is_some() {
+                continue;
+            }
+
+            let mut operation = json!({
+                "responses": {
+                    "default": { "description": "Response" }
+                }
+            });
+            if let Some(ref name) = route.name {
+                operation["operationId"] = json!(name);
+            }
+            if let Some(ref summary) = route.summary {
+                operation["summary"] = json!(summary);
+            }
+            if !route.tags.is_empty() {
+                operation["tags"] = json!(route.tags);
+            }
+            if !params.is_empty() {
+                operation["parameters"] = Value::Array(params.iter().map(|name| json!({
+                    "name": name,
+                    "in": "path",
+                    "required": true,
+                    "schema": { "type": "string" },
+                })).collect());
+            }
+            operations[method] = operation;
+        }
+
+        json!({
+            "openapi": "3.0.3",
+            "info": info,
+            "paths": paths,
+        })
+    }
+
+    /// Generate the OpenAPI document for these routes, and make a route handler
+    /// which serves it as JSON.  The document is generated once, so routes defined
+    /// later are not included.
+    pub fn handler<S, E>(&self, routes: &[RouteInfo])
+                         -> impl Fn(PluginData<S>)
+                                    -> Box<dyn Future<Item = PluginData<S>, Error = E>>
+                            + Send + Sync + 'static
+        where S: 'static,
+              E: 'static
+    {
+        let body = self.document(routes).to_string();
+        move |mut data: PluginData<S>| {
+            data.response.headers_mut().set(ContentType::json());
+            data.response.headers_mut().set(ContentLength(body.len() as u64));
+            data.response.set_body(body.clone());
+            data.response.set_status(StatusCode::Ok);
+            Box::new(::futures::future::ok(data)) as Box<dyn Future<Item = _, Error = _>>
+        }
+    }
+}
+
+/// The OpenAPI form of a route's path, e.g. `/users/{id}`, and its parameters
+fn openapi_path(route: &RouteInfo) -> Option<(String, Vec<String>)> {
+    let mut path = String::new();
+    let mut params = Vec::new();
+    for segment in &route.parsed.segments {
+        path.push('/');
+        match *segment {
+            Segment::Static(ref s) => path.push_str(s),
+            Segment::Param(ref name) | Segment::Wildcard(Some(ref name)) => {
+                path.push('{');
+                path.push_str(name);
+                path.push('}');
+                params.push(name.clone());
+            },
+            Segment::Wildcard(None) => return None,
+        }
+    }
+    if path.is_empty() {
+        path.push('/');
+    }
+    Some((path, params))
+}
@@ -0,0 +1,155 @@
+use std::collections::HashMap;
+use std::fmt;
+
+/// A segment of a route pattern.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Segment {
+    /// Matches exactly this text
+    Static(String),
+    /// `:name` matches any single non-empty segment
+    Param(String),
+    /// `*name` (or the anonymous `**` or `*`) matches the remainder of the path,
+    /// even if that remainder is empty
+    Wildcard(Option<String>),
+}
+
+/// A parsed route pattern, such as `/users/:id` or `/static/**`
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Pattern {
+    pub segments: Vec<Segment>,
+    /// Whether any segment captures part of the path
+    captures: bool,
+}
+
+impl Pattern {
+    /// Parse a pattern.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the pattern has segments after a wildcard, or has a parameter
+    /// without a name.
+    pub fn parse(pattern: &str) -> Pattern {
+        let mut segments: Vec<Segment> = Vec::new();
+        for seg in split_path(pattern) {
+            if let Some(&Segment::Wildcard(_)) = segments.last() {
+                panic!("Route pattern {:?} has segments after its wildcard", pattern);
+            }
+            let segment = if seg == "**" || seg == "*" {
+                Segment::Wildcard(None)
+            } else if let Some(name) = seg.strip_prefix('*') {
+                Segment::Wildcard(Some(name.to_owned()))
+            } else if let Some(name) = seg.strip_prefix(':') {
+                if name.is_empty() {
+                    panic!("Route pattern {:?} has an unnamed parameter", pattern);
+                }
+                Segment::Param(name.to_owned())
+            } else {
+                Segment::Static(seg.to_owned())
+            };
+            segments.push(segment);
+        }
+        Pattern::from_segments(segments)
+    }
+
+    /// Parse a prefix, under which other patterns will be placed.  Trailing
+    /// slashes are ignored, so that `/` is an empty prefix.
+    pub fn parse_prefix(prefix: &str) -> Pattern {
+        let prefix = prefix.trim_end_matches('/');
+        if prefix.is_empty() {
+            Pattern::from_segments(Vec::new())
+        } else {
+            Pattern::parse(prefix)
+        }
+    }
+
+    pub fn from_segments(segments: Vec<Segment>) -> Pattern {
+        let captures = segments.iter().any(|s| match *s {
+            Segment::Static(_) | Segment::Wildcard(None) => false,
+            Segment::Param(_) | Segment::Wildcard(Some(_)) => true,
+        });
+        Pattern { segments, captures }
+    }
+
+    /// This pattern placed under a prefix.  A pattern of `/` becomes the prefix
+    /// itself.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the prefix ends in a wildcard.
+    pub fn prefixed(&self, prefix: &Pattern) -> Pattern {
+        if let Some(&Segment::Wildcard(_)) = prefix.segments.last() {
+            panic!("Route prefix {:?} ends in a wildcard", prefix);
+        }
+        let mut segments = prefix.segments.clone();
+        if segments.is_empty() || self.segments != [Segment::Static(String::new())] {
+            segments.extend(self.segments.iter().cloned());
+        }
+        Pattern::from_segments(segments)
+    }
+
+    /// The part of a path matched by the final wildcard of this pattern.  The path
+    /// must already be known to match.
+    pub fn remainder<'a>(&self, path: &'a str) -> &'a str {
+        let mut rest = path.strip_prefix('/').unwrap_or(path);
+        for segment in &self.segments {
+            if let Segment::Wildcard(_) = *segment {
+                return rest;
+            }
+            rest = match rest.find('/') {
+                Some(i) => &rest[i + 1..],
+                None => "",
+            };
+        }
+        ""
+    }
+
+    /// Capture the parameters from a path which is already known to match this
+    /// pattern.  Nothing is allocated if the pattern has no named parameters.
+    pub fn captures(&self, path: &str, params: &mut HashMap<String, String>) {
+        if !self.captures {
+            return;
+        }
+        let mut rest = Some(path.strip_prefix('/').unwrap_or(path));
+        for segment in &self.segments {
+            let current = rest.unwrap_or("");
+            let (seg, remainder) = match current.find('/') {
+                Some(i) => (&current[..i], Some(&current[i + 1..])),
+                None => (current, None),
+            };
+            match *segment {
+                Segment::Static(_) => {},
+                Segment::Param(ref name) => {
+                    params.insert(name.clone(), seg.to_owned());
+                },
+                Segment::Wildcard(Some(ref name)) => {
+                    params.insert(name.clone(), current.to_owned());
+                    return;
+                },
+                Segment::Wildcard(None) => return,
+            }
+            rest = remainder;
+        }
+    }
+}
+
+impl fmt::Display for Pattern {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.segments.is_empty() {
+            return write!(f, "/");
+        }
+        for segment in &self.segments {
+            match *segment {
+                Segment::Static(ref s) => write!(f, "/{}", s)?,
+                Segment::Param(ref name) => write!(f, "/:{}", name)?,
+                Segment::Wildcard(Some(ref name)) => write!(f, "/*{}", name)?,
+                Segment::Wildcard(None) => write!(f, "/**")?,
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Split a path into its segments, ignoring the leading slash
+pub fn split_path(path: &str) -> ::std::str::Split<'_, char> {
+    path.strip_prefix('/').unwrap_or(path).split('/')
+}
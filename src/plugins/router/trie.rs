@@ -0,0 +1,138 @@
+use crate::plugins::router::pattern::{Pattern, Segment};
+
+/// A compiled route trie, keyed by path segment.
+///
+/// A trie is never modified once it is in use; the `Router` builds a new one and
+/// swaps it in whenever the routes change.  Finding a route therefore needs no
+/// locks, and allocates nothing.
+pub struct Node<T> {
+    /// Children for static segments.  These are searched linearly, which beats a
+    /// binary search at the sizes routers usually have.
+    statics: Vec<(String, Node<T>)>,
+    /// The child for a parameter segment
+    param: Option<Box<Node<T>>>,
+    /// Routes whose pattern ends in a wildcard at this point
+    wildcards: Vec<T>,
+    /// Routes whose pattern ends exactly at this point
+    leaves: Vec<T>,
+}
+
+impl<T> Default for Node<T> {
+    fn default() -> Node<T> {
+        Node {
+            statics: Vec::new(),
+            param: None,
+            wildcards: Vec::new(),
+            leaves: Vec::new(),
+        }
+    }
+}
+
+impl<T> Node<T> {
+    /// Add a route to the trie.  Routes which end at the same point are kept in
+    /// the order they are added.
+    pub fn insert(&mut self, pattern: &Pattern, item: T) {
+        let mut node = self;
+        for segment in &pattern.segments {
+            node = match *segment {
+                Segment::Static(ref s) => {
+                    let i = match node.statics.iter().position(|c| &c.0 == s) {
+                        Some(i) => i,
+                        None => {
+                            node.statics.push((s.clone(), Node::default()));
+                            node.statics.len() - 1
+                        },
+                    };
+                    &mut node.statics[i].1
+                },
+                Segment::Param(_) => {
+                    node.param.get_or_insert_with(Default::default)
+                },
+                Segment::Wildcard(_) => {
+                    node.wildcards.push(item);
+                    return;
+                },
+            };
+        }
+        node.leaves.push(item);
+    }
+
+    /// Find the first acceptable route matching the path.
+    ///
+    /// Static segments are tried before parameters, and parameters before
+    /// wildcards, backtracking when a branch has no acceptable route.
+    pub fn find<F>(&self, path: &str, accept: &F) -> Option<&T>
+        where F: Fn(&T) -> bool
+    {
+        self.find_from(Some(path.strip_prefix('/').unwrap_or(path)), accept)
+    }
+
+    fn find_from<F>(&self, rest: Option<&str>, accept: &F) -> Option<&T>
+        where F: Fn(&T) -> bool
+    {
+        let current = match rest {
+            Some(current) => current,
+            None => {
+                return self.leaves.iter().find(|t| accept(t))
+                    .or_else(|| self.wildcards.iter().find(|t| accept(t)));
+            },
+        };
+
+        let (seg, remainder) = match current.find('/') {
+            Some(i) => (&current[..i], Some(&current[i + 1..])),
+            None => (current, None),
+        };
+
+        if let Some(child) = self.statics.iter().find(|c| c.0 == seg) {
+            if let Some(t) = child.1.find_from(remainder, accept) {
+                return Some(t);
+            }
+        }
+
+        if !seg.is_empty() {
+            if let Some(ref param) = self.param {
+                if let Some(t) = param.find_from(remainder, accept) {
+                    return Some(t);
+                }
+            }
+        }
+
+        self.wildcards.iter().find(|t| accept(t))
+    }
+
+    /// Visit every route matching the path, whichever branch it is on
+    pub fn for_each_match<F>(&self, path: &str, visit: &mut F)
+        where F: FnMut(&T)
+    {
+        self.for_each_from(Some(path.strip_prefix('/').unwrap_or(path)), visit)
+    }
+
+    fn for_each_from<F>(&self, rest: Option<&str>, visit: &mut F)
+        where F: FnMut(&T)
+    {
+        self.wildcards.iter().for_each(&mut *visit);
+
+        let current = match rest {
+            Some(current) => current,
+            None => {
+                self.leaves.iter().for_each(visit);
+                return;
+            },
+        };
+
+        let (seg, remainder) = match current.find('/') {
+            Some(i) => (&current[..i], Some(&current[i + 1..])),
+            None => (current, None),
+        };
+
+        if let Some(child) = self.statics.iter().find(|c| c.0 == seg) {
+            child.1.for_each_from(remainder, visit);
+        }
+
+        if !seg.is_empty() {
+            if let Some(ref param) = self.param {
+                param.for_each_from(remainder, visit);
+            }
+        }
+    }
+}
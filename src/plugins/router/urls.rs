@@ -0,0 +1,129 @@
+use std::collections::HashMap;
+use std::error::Error as StdError;
+use std::fmt;
+use percent_encoding::utf8_percent_encode;
+use crate::path::SEGMENT;
+use crate::plugins::router::pattern::{Pattern, Segment};
+
+/// An error generating a URL for a named route
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum UrlError {
+    /// No route has this name
+    UnknownRoute(String),
+    /// The route needs a parameter which was not supplied
+    MissingParam {
+        route: String,
+        param: String,
+    },
+    /// A parameter's value would not survive path normalization: it is empty, or
+    /// it is (or, for a wildcard, has a part which is) `.` or `..`, or it has a NUL
+    /// or (except for a wildcard) a `/`
+    InvalidParam {
+        route: String,
+        param: String,
+    },
+}
+
+impl fmt::Display for UrlError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match *self {
+            UrlError::UnknownRoute(ref name) => write!(f, "No route is named {:?}", name),
+            UrlError::MissingParam { ref route, ref param } =>
+                write!(f, "Route {:?} needs parameter {:?}", route, param),
+            UrlError::InvalidParam { ref route, ref param } =>
+                write!(f, "Parameter {:?} of route {:?} cannot be used in a path",
+                       param, route),
+        }
+    }
+}
+
+impl StdError for UrlError { }
+
+/// The named routes of a `Router`, for generating URLs
+#[derive(Debug, Default)]
+pub struct Urls {
+    routes: HashMap<String, Pattern>,
+}
+
+impl Urls {
+    pub(crate) fn insert(&mut self, name: String, pattern: Pattern) {
+        self.routes.insert(name, pattern);
+    }
+
+    pub(crate) fn is_empty(&self) -> bool {
+        self.routes.is_empty()
+    }
+
+    /// Add all the routes of another table, placed under a prefix
+    pub(crate) fn extend_prefixed(&mut self, prefix: &Pattern, other: &Urls) {
+        for (name, pattern) in &other.routes {
+            self.routes.insert(name.clone(), pattern.prefixed(prefix));
+        }
+    }
+
+    /// Generate the path for the named route, substituting the parameters.
+    ///
+    /// The segments of the route and the parameter values are percent-encoded.
+    /// Wildcard parameters may contain `/`, which is kept; each part between the
+    /// slashes is encoded separately.
+    ///
+    /// Values which the router could not route back to the same parameters are
+    /// refused: `.` and `..`, values with a NUL, and empty values or values with a
+    /// `/` of parameters other than wildcards.
+    pub fn url_for(&self, name: &str, params: &[(&str, &str)]) -> Result<String, UrlError>
+    {
+        let pattern = match self.routes.get(name) {
+            Some(pattern) => pattern,
+            None => return Err(UrlError::UnknownRoute(name.to_owned())),
+        };
+
+        let lookup = |param: &str| {
+            params.iter().find(|p| p.0 == param).map(|p| p.1).ok_or_else(|| {
+                UrlError::MissingParam {
+                    route: name.to_owned(),
+                    param: param.to_owned(),
+                }
+            })
+        };
+        let invalid = |param: &str| UrlError::InvalidParam {
+            route: name.to_owned(),
+            param: param.to_owned(),
+        };
+
+        let mut url = String::new();
+        for segment in &pattern.segments {
+            match *segment {
+                Segment::Static(ref s) => {
+                    url.push('/');
+                    url.extend(utf8_percent_encode(s, SEGMENT));
+                },
+                Segment::Param(ref param) => {
+                    let value = lookup(param)?;
+                    if matches!(value, "" | "." | "..") || value.contains(&['/', '\0'][..]) {
+                        return Err(invalid(param));
+                    }
+                    url.push('/');
+                    url.extend(utf8_percent_encode(value, SEGMENT));
+                },
+                Segment::Wildcard(Some(ref param)) => {
+                    url.push('/');
+                    let value = lookup(param)?;
+                    let dots = value.split('/').any(|part| part == "." || part == "..");
+                    if dots || value.contains('\0') {
+                        return Err(invalid(param));
+                    }
+                    for (i, part) in value.split('/').enumerate() {
+                        if i > 0 {
+                            url.push('/');
+                        }
+                        url.extend(utf8_percent_encode(part, SEGMENT));
+                    }
+                },
+                Segment::Wildcard(None) => {
+                    url.push('/');
+                },
+            }
+        }
+        Ok(url)
+    }
+}
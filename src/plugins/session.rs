@@ -38,9 +38,9 @@ impl Session
     /// allow javascript to access it)
     pub fn new(cookie_name: String, secure: bool, http_only: bool) -> Session {
         Session {
-            cookie_name: cookie_name,
-            secure: secure,
-            http_only: http_only,
+            cookie_name,
+            secure,
+            http_only,
             respect_dnt_ad_absurdum: false,
         }
     }
@@ -65,7 +65,7 @@ impl<S,E> Plugin<S,E> for Session
             if let Some(header) = data.request.headers().get::<Dnt>() {
                 match *header {
                     Dnt(ref s) => {
-                        if &*s != "0" {
+                        if s != "0" {
                             dnt = true;
                         }
                     },
@@ -86,7 +86,7 @@ impl<S,E> Plugin<S,E> for Session
 
         let mut maybe_key: Option<String> = None;
         if let Some(cookie_header) = data.request.headers().get::<CookieHeader>() {
-            if let Some(cookie_value) = cookie_header.get(&*self.cookie_name) {
+            if let Some(cookie_value) = cookie_header.get(&self.cookie_name) {
                 maybe_key = Some(cookie_value.to_owned());
             }
         }
@@ -0,0 +1,49 @@
+//! Parsing of `name=value` pairs, as found in query strings and URL-encoded form
+//! bodies.
+
+/// Parsed `name=value` pairs, in the order they were given.  A name may appear
+/// more than once.
+///
+/// Names and values are percent-decoded, and `+` is decoded as a space.  Invalid
+/// UTF-8 is replaced with U+FFFD.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Query {
+    pairs: Vec<(String, String)>,
+}
+
+impl Query {
+    /// Parse `application/x-www-form-urlencoded` input, such as a query string
+    /// (without the leading `?`)
+    pub fn parse(input: &[u8]) -> Query {
+        Query {
+            pairs: form_urlencoded::parse(input).into_owned().collect(),
+        }
+    }
+
+    /// The first value given for a name
+    pub fn get(&self, name: &str) -> Option<&str> {
+        self.pairs.iter().find(|p| p.0 == name).map(|p| &*p.1)
+    }
+
+    /// All the values given for a name, in order
+    pub fn get_all<'a>(&'a self, name: &'a str) -> impl Iterator<Item = &'a str> + 'a {
+        self.pairs.iter().filter(move |p| p.0 == name).map(|p| &*p.1)
+    }
+
+    pub fn contains(&self, name: &str) -> bool {
+        self.pairs.iter().any(|p| p.0 == name)
+    }
+
+    /// All the pairs, in order
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &str)> {
+        self.pairs.iter().map(|p| (&*p.0, &*p.1))
+    }
+
+    pub fn len(&self) -> usize {
+        self.pairs.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.pairs.is_empty()
+    }
+}
@@ -14,7 +14,7 @@ impl<S> Shared<S>
     pub fn new(num_threads: usize, state: S) -> Shared<S> {
         Shared {
             pool: CpuPool::new(num_threads),
-            state: state,
+            state,
         }
     }
 }
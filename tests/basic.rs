@@ -6,7 +6,7 @@ extern crate futures;
 use std::io::Error as IoError;
 use std::sync::Arc;
 use futures::Future;
-use hyper::{Method, StatusCode};
+use hyper::Method;
 use pemmican::{Pemmican, Config, PluginData, Plugin};
 
 // This is the static router
@@ -18,7 +18,7 @@ impl Plugin<(),IoError> for MyRouter {
         match (data.request.path(), data.request.method()) {
             ("/", &Method::Get) => home(data),
             _ => {
-                data.response.set_status(StatusCode::NotFound);
+                data.not_found();
                 Box::new(futures::future::ok( data ))
             }
         }
@@ -29,8 +29,7 @@ impl Plugin<(),IoError> for MyRouter {
 fn home(mut data: PluginData<()>)
         -> Box<dyn Future<Item = PluginData<()>, Error = IoError>>
 {
-    data.response.set_body(format!("Hello World!"));
-    data.response.set_status(StatusCode::Ok);
+    data.text("Hello World!");
     Box::new(futures::future::ok( data ))
 }
 
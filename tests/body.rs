@@ -0,0 +1,51 @@
+extern crate pemmican;
+extern crate futures;
+extern crate futures_cpupool;
+
+use std::io::{self, Read};
+use std::sync::Arc;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::thread;
+use std::time::Duration;
+use futures::Stream;
+use futures_cpupool::CpuPool;
+use pemmican::body::{from_reader, CHUNK_SIZE};
+
+// An endless reader which counts how many bytes have been read from it
+struct Counting {
+    limit: usize,
+    read: Arc<AtomicUsize>,
+}
+
+impl Read for Counting {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let read = self.read.load(Ordering::SeqCst);
+        let len = buf.len().min(self.limit - read);
+        for b in &mut buf[..len] {
+            *b = b'x';
+        }
+        self.read.fetch_add(len, Ordering::SeqCst);
+        Ok(len)
+    }
+}
+
+#[test]
+fn streams_with_backpressure()
+{
+    let pool = CpuPool::new(1);
+    let read = Arc::new(AtomicUsize::new(0));
+    let limit = 20 * CHUNK_SIZE + 100;
+    let body = from_reader(&pool, Counting { limit, read: read.clone() });
+
+    // Nothing is reading the body, so the reader is not drained
+    thread::sleep(Duration::from_millis(100));
+    assert!(read.load(Ordering::SeqCst) <= 3 * CHUNK_SIZE);
+
+    let mut total = 0;
+    for chunk in body.wait() {
+        let chunk = chunk.unwrap();
+        assert!(chunk.len() <= CHUNK_SIZE);
+        total += chunk.len();
+    }
+    assert_eq!(total, limit);
+}
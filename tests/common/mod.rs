@@ -0,0 +1,58 @@
+// Fixtures shared by the tests.  Not every test uses all of them.
+#![allow(dead_code)]
+
+use std::io::Error as IoError;
+use std::sync::Arc;
+use futures::{Future, Stream};
+use hyper::Method;
+use hyper::server::{Request, Response};
+use pemmican::{PluginData, Plugin, Shared};
+
+// A request with extra headers
+pub fn request(method: Method, path: &str, headers: &[(&str, &str)]) -> Request
+{
+    let mut request = Request::new(method, path.parse().unwrap());
+    for &(name, value) in headers {
+        request.headers_mut().set_raw(name.to_owned(), value.to_owned());
+    }
+    request
+}
+
+// The data a plugin is given for a request
+pub fn data(request: Request) -> PluginData<()>
+{
+    PluginData::new(Arc::new(Shared::new(2, ())), request)
+}
+
+// Run a request through a plugin
+pub fn call<P>(plugin: &P, method: Method, path: &str) -> PluginData<()>
+    where P: Plugin<(), IoError> + ?Sized
+{
+    call_with(plugin, method, path, &[])
+}
+
+// Run a request with extra headers through a plugin
+pub fn call_with<P>(plugin: &P, method: Method, path: &str, headers: &[(&str, &str)])
+                    -> PluginData<()>
+    where P: Plugin<(), IoError> + ?Sized
+{
+    plugin.handle(data(request(method, path, headers))).wait().unwrap()
+}
+
+// The body of a response
+pub fn response_body(response: Response) -> Vec<u8>
+{
+    response.body().concat2().wait().unwrap().to_vec()
+}
+
+// The body of the response a plugin gave
+pub fn body(data: PluginData<()>) -> Vec<u8>
+{
+    response_body(data.response)
+}
+
+// The body of the response a plugin gave, as text
+pub fn text(data: PluginData<()>) -> String
+{
+    String::from_utf8(body(data)).unwrap()
+}
@@ -0,0 +1,122 @@
+extern crate pemmican;
+extern crate hyper;
+extern crate futures;
+extern crate tempfile;
+extern crate tokio_service;
+
+mod common;
+
+use std::io::Error as IoError;
+use std::fs;
+use std::sync::Arc;
+use futures::Future;
+use hyper::{mime, Method, StatusCode};
+use hyper::header::{ContentLength, ContentType};
+use hyper::Headers;
+use hyper::server::Request;
+use tokio_service::Service;
+use pemmican::{Pemmican, Config, PluginData, Plugin};
+use pemmican::plugins::{ErrorPages, Htdocs};
+
+// Fails with a 500 and no body at /broken or an explicitly empty one at /empty,
+// and answers /teapot and /gone itself
+struct App;
+impl Plugin<(),IoError> for App {
+    fn handle(&self, mut data: PluginData<()>)
+              -> Box<dyn Future<Item = PluginData<()>, Error = IoError>>
+    {
+        match data.request.path() {
+            "/broken" => data.response.set_status(StatusCode::InternalServerError),
+            "/empty" => {
+                data.response.set_status(StatusCode::InternalServerError);
+                data.response.headers_mut().set(ContentLength(0));
+                data.response.set_body(Vec::new());
+            },
+            "/gone" => data.not_found(),
+            "/teapot" => {
+                data.text("I am a teapot");
+                data.response.set_status(StatusCode::ImATeapot);
+            },
+            _ => { },
+        }
+        Box::new(futures::future::ok(data))
+    }
+}
+
+fn call(pemmican: &Pemmican<(), IoError>, method: Method, path: &str)
+        -> (StatusCode, Headers, Vec<u8>)
+{
+    let response = pemmican.call(Request::new(method, path.parse().unwrap()))
+        .wait().unwrap();
+    let (status, headers) = (response.status(), response.headers().clone());
+    (status, headers, common::response_body(response))
+}
+
+#[test]
+fn error_pages()
+{
+    let docroot = tempfile::tempdir().unwrap();
+    fs::write(docroot.path().join("index.html"), "Home").unwrap();
+    fs::create_dir(docroot.path().join("errors")).unwrap();
+    fs::write(docroot.path().join("errors/404.html"), "<h1>Nothing here</h1>").unwrap();
+
+    let mut error_pages = ErrorPages::new(docroot.path());
+    error_pages.set_file(StatusCode::NotFound, "errors/404.html");
+    error_pages.set_inline(StatusCode::InternalServerError, mime::TEXT_PLAIN_UTF_8,
+                           "{status} {reason}: sorry!");
+    error_pages.set_inline(StatusCode::ImATeapot, mime::TEXT_PLAIN_UTF_8, "Unused");
+    let pemmican: Pemmican<(), IoError> = Pemmican::new(
+        Config::default(),
+        vec![Arc::new(Box::new(App)),
+             Arc::new(Box::new(Htdocs::new(docroot.path(), Some("index.html".to_owned())))),
+             Arc::new(Box::new(error_pages))],
+        ());
+
+    // Pages from files, keeping the status
+    let (status, headers, body) = call(&pemmican, Method::Get, "/missing");
+    assert_eq!(status, StatusCode::NotFound);
+    assert_eq!(headers.get::<ContentType>(), Some(&ContentType(mime::TEXT_HTML_UTF_8)));
+    assert_eq!(headers.get::<ContentLength>(), Some(&ContentLength(21)));
+    assert_eq!(body, b"<h1>Nothing here</h1>");
+
+    // And inline templates
+    let (status, headers, body) = call(&pemmican, Method::Get, "/broken");
+    assert_eq!(status, StatusCode::InternalServerError);
+    assert_eq!(headers.get::<ContentType>(), Some(&ContentType(mime::TEXT_PLAIN_UTF_8)));
+    assert_eq!(body, b"500 Internal Server Error: sorry!");
+    let (status, headers, body) = call(&pemmican, Method::Get, "/empty");
+    assert_eq!(status, StatusCode::InternalServerError);
+    assert_eq!(headers.get::<ContentLength>(), Some(&ContentLength(33)));
+    assert_eq!(body, b"500 Internal Server Error: sorry!");
+
+    // Without a body for HEAD requests
+    let (status, headers, body) = call(&pemmican, Method::Head, "/missing");
+    assert_eq!(status, StatusCode::NotFound);
+    assert_eq!(headers.get::<ContentLength>(), Some(&ContentLength(21)));
+    assert!(body.is_empty());
+
+    // Responses with bodies are left alone
+    let (status, _, body) = call(&pemmican, Method::Get, "/teapot");
+    assert_eq!(status, StatusCode::ImATeapot);
+    assert_eq!(body, b"I am a teapot");
+    let (status, _, body) = call(&pemmican, Method::Get, "/gone");
+    assert_eq!(status, StatusCode::NotFound);
+    assert_eq!(body, b"Not Found");
+    assert_eq!(call(&pemmican, Method::Get, "/").2, b"Home");
+}
+
+#[test]
+fn missing_pages()
+{
+    let docroot = tempfile::tempdir().unwrap();
+    let mut error_pages = ErrorPages::new(docroot.path());
+    error_pages.set_file(StatusCode::NotFound, "404.html");
+    let pemmican: Pemmican<(), IoError> = Pemmican::new(
+        Config::default(), vec![Arc::new(Box::new(error_pages))], ());
+
+    // A page which cannot be read leaves the response as it was
+    let (status, headers, body) = call(&pemmican, Method::Get, "/missing");
+    assert_eq!(status, StatusCode::NotFound);
+    assert!(!headers.has::<ContentType>());
+    assert!(body.is_empty());
+}
@@ -0,0 +1,125 @@
+extern crate pemmican;
+extern crate hyper;
+extern crate futures;
+
+mod common;
+
+use std::io::Read;
+use std::thread;
+use futures::{Future, Sink, Stream};
+use hyper::{Body, Chunk, Method, StatusCode};
+use pemmican::PluginData;
+use pemmican::forms::{Limits, PartData};
+
+// A request whose body arrives in chunks of the given size
+fn request(content_type: &str, body: &[u8], chunk_size: usize) -> PluginData<()>
+{
+    let (sender, stream) = Body::pair();
+    let chunks: Vec<Chunk> = body.chunks(chunk_size).map(|c| c.to_vec().into()).collect();
+    thread::spawn(move || {
+        let mut sender = sender;
+        for chunk in chunks {
+            sender = match sender.send(Ok(chunk)).wait() {
+                Ok(sender) => sender,
+                Err(_) => return, // the body was abandoned
+            };
+        }
+    });
+
+    let mut request = common::request(Method::Post, "/upload", &[("Content-Type", content_type)]);
+    request.set_body(stream);
+    common::data(request)
+}
+
+const MULTIPART: &str = "preamble\r\n\
+    --XyZ\r\n\
+    Content-Disposition: form-data; name=\"title\"\r\n\
+    \r\n\
+    Holiday \"snaps\"\r\n\
+    --XyZ\r\n\
+    Content-Disposition: form-data; name=\"photo\"; filename=\"beach; sunset.txt\"\r\n\
+    Content-Type: text/plain\r\n\
+    \r\n\
+    The sun sets over the beach, slowly, and then all at once.\r\n\
+    --XyZ\r\n\
+    Content-Disposition: form-data; name=\"empty\"\r\n\
+    \r\n\
+    \r\n\
+    --XyZ--\r\n\
+    epilogue";
+
+#[test]
+fn urlencoded()
+{
+    let mut data = request("application/x-www-form-urlencoded",
+                           b"name=Mike+D&lang=en&lang=fr", 3);
+    let form = data.form(&Limits::default()).wait().unwrap();
+    assert_eq!(form.get("name"), Some("Mike D"));
+    assert_eq!(form.get_all("lang").collect::<Vec<_>>(), vec!["en", "fr"]);
+
+    let limits = Limits { max_total_size: 10, ..Limits::default() };
+    let mut data = request("application/x-www-form-urlencoded", b"name=Mike+Dilger", 3);
+    assert_eq!(data.form(&limits).wait().unwrap_err().status(), StatusCode::PayloadTooLarge);
+
+    let mut data = request("text/plain", b"name=Mike", 3);
+    assert_eq!(data.form(&Limits::default()).wait().unwrap_err().status(),
+               StatusCode::UnsupportedMediaType);
+}
+
+#[test]
+fn multipart()
+{
+    for &chunk_size in &[1, 5, 1024] {
+        let mut data = request("multipart/form-data; boundary=XyZ", MULTIPART.as_bytes(),
+                               chunk_size);
+        let limits = Limits { memory_threshold: 16, ..Limits::default() };
+        let parts = data.multipart(&limits).unwrap().collect().wait().unwrap();
+        assert_eq!(parts.len(), 3);
+
+        assert_eq!(parts[0].name, "title");
+        assert_eq!(parts[0].filename, None);
+        assert_eq!(parts[0].text(), Some("Holiday \"snaps\""));
+
+        // The file is over the memory threshold, so was spooled
+        assert_eq!(parts[1].name, "photo");
+        assert_eq!(parts[1].filename, Some("beach; sunset.txt".to_owned()));
+        assert_eq!(parts[1].content_type, Some("text/plain".to_owned()));
+        assert_eq!(parts[1].size, 58);
+        match parts[1].data {
+            PartData::File(ref file) => {
+                let mut contents = String::new();
+                file.reopen().unwrap().read_to_string(&mut contents).unwrap();
+                assert_eq!(contents, "The sun sets over the beach, slowly, and then all at once.");
+            },
+            PartData::Memory(_) => panic!("File was not spooled"),
+        }
+
+        assert_eq!(parts[2].text(), Some(""));
+    }
+}
+
+#[test]
+fn multipart_errors()
+{
+    let collect = |content_type: &str, body: &str, limits: &Limits| {
+        let mut data = request(content_type, body.as_bytes(), 7);
+        data.multipart(limits).and_then(|parts| parts.collect().wait()).unwrap_err().status()
+    };
+    let form_data = "multipart/form-data; boundary=XyZ";
+
+    let limits = Limits { max_field_size: 20, ..Limits::default() };
+    assert_eq!(collect(form_data, MULTIPART, &limits), StatusCode::PayloadTooLarge);
+    let limits = Limits { max_total_size: 100, ..Limits::default() };
+    assert_eq!(collect(form_data, MULTIPART, &limits), StatusCode::PayloadTooLarge);
+    let limits = Limits {
+        memory_threshold: 16,
+        temp_dir: Some("/nonexistent/pemmican".into()),
+        ..Limits::default()
+    };
+    assert_eq!(collect(form_data, MULTIPART, &limits), StatusCode::InternalServerError);
+
+    let limits = Limits::default();
+    assert_eq!(collect(form_data, &MULTIPART[..100], &limits), StatusCode::BadRequest);
+    assert_eq!(collect("multipart/form-data", MULTIPART, &limits), StatusCode::BadRequest);
+    assert_eq!(collect("text/plain", MULTIPART, &limits), StatusCode::UnsupportedMediaType);
+}
@@ -2,13 +2,22 @@
 extern crate pemmican;
 extern crate hyper;
 extern crate futures;
+extern crate tempfile;
+
+mod common;
 
 use std::io::Error as IoError;
+use std::fs;
 use std::sync::Arc;
 use futures::Future;
-use hyper::{Method, StatusCode};
-use pemmican::{Pemmican, Config, PluginData};
+use hyper::{mime, Method, StatusCode};
+use hyper::header::{AcceptRanges, ContentEncoding, ContentLength, ContentRange, ContentType,
+                    Encoding, ETag, LastModified, Location, RangeUnit};
+use pemmican::{Pemmican, Config, PluginData, Plugin};
+use pemmican::mime_types::MimeTypes;
 use pemmican::plugins::{Router, Htdocs};
+use pemmican::plugins::htdocs::{Access, Autoindex, Cache, ETags, Fallback, Memory, Symlinks};
+use common::{body, call, call_with, text};
 
 // This is our home page handler
 fn home(mut data: PluginData<()>)
@@ -43,3 +52,496 @@ fn main()
                          futures::future::ok(()) // this completes immediately
     );
 }
+
+#[test]
+fn serves_files()
+{
+    let htdocs = Htdocs::new(".", None);
+    let expected = fs::read("Cargo.lock").unwrap();
+
+    let data = call(&htdocs, Method::Get, "/Cargo.lock");
+    assert_eq!(data.response.status(), StatusCode::Ok);
+    assert_eq!(data.response.headers().get::<ContentLength>(),
+               Some(&ContentLength(expected.len() as u64)));
+    assert_eq!(body(data), expected);
+
+    let data = call(&htdocs, Method::Head, "/Cargo.lock");
+    assert_eq!(data.response.headers().get::<ContentLength>(),
+               Some(&ContentLength(expected.len() as u64)));
+    assert!(body(data).is_empty());
+
+    assert_eq!(call(&htdocs, Method::Get, "/no/such/file").response.status(),
+               StatusCode::NotFound);
+    assert_eq!(call(&htdocs, Method::Get, "/src").response.status(), StatusCode::NotFound);
+}
+
+#[test]
+fn content_types()
+{
+    let content_type = |htdocs: &Htdocs, path: &str| {
+        call(htdocs, Method::Get, path).response.headers().get::<ContentType>()
+            .map(|c| c.to_string())
+    };
+
+    let mut htdocs = Htdocs::new(".", None);
+    assert_eq!(content_type(&htdocs, "/README.md"),
+               Some("text/markdown; charset=utf-8".to_owned()));
+    assert_eq!(content_type(&htdocs, "/Cargo.lock"),
+               Some("application/octet-stream".to_owned()));
+
+    let mut types = MimeTypes::new();
+    types.insert("lock", "text/plain; charset=utf-8".parse().unwrap());
+    types.set_default(None);
+    htdocs.set_mime_types(types);
+    assert_eq!(content_type(&htdocs, "/Cargo.lock"),
+               Some("text/plain; charset=utf-8".to_owned()));
+    assert_eq!(content_type(&htdocs, "/LICENSE-MIT"), None);
+}
+
+#[test]
+fn conditional_requests()
+{
+    let mut htdocs = Htdocs::new(".", None);
+    let data = call(&htdocs, Method::Get, "/Cargo.toml");
+    let etag = data.response.headers().get::<ETag>().unwrap().to_string();
+    let modified = data.response.headers().get::<LastModified>().unwrap().to_string();
+    assert!(!etag.starts_with("W/"));
+
+    let status = |htdocs: &Htdocs, headers: &[(&str, &str)]| {
+        call_with(htdocs, Method::Get, "/Cargo.toml", headers).response.status()
+    };
+    let earlier = "Sat, 01 Jan 2000 00:00:00 GMT";
+    let later = "Fri, 01 Jan 2100 00:00:00 GMT";
+
+    assert_eq!(status(&htdocs, &[("If-None-Match", &etag)]), StatusCode::NotModified);
+    assert_eq!(status(&htdocs, &[("If-None-Match", &format!("\"other\", W/{}", etag))]),
+               StatusCode::NotModified);
+    assert_eq!(status(&htdocs, &[("If-None-Match", "*")]), StatusCode::NotModified);
+    assert_eq!(status(&htdocs, &[("If-None-Match", "\"other\"")]), StatusCode::Ok);
+    assert_eq!(status(&htdocs, &[("If-Modified-Since", &modified)]), StatusCode::NotModified);
+    assert_eq!(status(&htdocs, &[("If-Modified-Since", earlier)]), StatusCode::Ok);
+    // If-None-Match takes precedence over If-Modified-Since
+    assert_eq!(status(&htdocs, &[("If-None-Match", "\"other\""), ("If-Modified-Since", later)]),
+               StatusCode::Ok);
+
+    assert_eq!(status(&htdocs, &[("If-Match", &etag)]), StatusCode::Ok);
+    assert_eq!(status(&htdocs, &[("If-Match", "\"other\"")]), StatusCode::PreconditionFailed);
+    assert_eq!(status(&htdocs, &[("If-Match", &format!("W/{}", etag))]), StatusCode::PreconditionFailed);
+    assert_eq!(status(&htdocs, &[("If-Unmodified-Since", later)]), StatusCode::Ok);
+    assert_eq!(status(&htdocs, &[("If-Unmodified-Since", earlier)]), StatusCode::PreconditionFailed);
+
+    let data = call_with(&htdocs, Method::Get, "/Cargo.toml", &[("If-None-Match", &etag)]);
+    assert_eq!(data.response.headers().get::<ETag>().unwrap().to_string(), etag);
+    assert!(body(data).is_empty());
+
+    htdocs.set_etags(ETags::Weak);
+    let data = call(&htdocs, Method::Get, "/Cargo.toml");
+    let weak = data.response.headers().get::<ETag>().unwrap().to_string();
+    assert_eq!(weak, format!("W/{}", etag));
+    assert_eq!(status(&htdocs, &[("If-None-Match", &weak)]), StatusCode::NotModified);
+
+    htdocs.set_etags(ETags::Off);
+    let data = call(&htdocs, Method::Get, "/Cargo.toml");
+    assert!(!data.response.headers().has::<ETag>());
+    assert!(data.response.headers().has::<LastModified>());
+}
+
+#[test]
+fn ranges()
+{
+    let htdocs = Htdocs::new(".", None);
+    let file = fs::read("Cargo.lock").unwrap();
+    let len = file.len();
+    let range = |headers: &[(&str, &str)]| {
+        let data = call_with(&htdocs, Method::Get, "/Cargo.lock", headers);
+        let status = data.response.status();
+        let content_range = data.response.headers().get::<ContentRange>().map(|c| c.to_string());
+        let content_type = data.response.headers().get::<ContentType>().map(|c| c.to_string());
+        let length = data.response.headers().get::<ContentLength>().map(|c| c.0);
+        let body = body(data);
+        assert_eq!(length, Some(body.len() as u64));
+        (status, content_range, content_type, body)
+    };
+
+    let data = call(&htdocs, Method::Get, "/Cargo.lock");
+    assert_eq!(data.response.headers().get::<AcceptRanges>(),
+               Some(&AcceptRanges(vec![RangeUnit::Bytes])));
+
+    let (status, content_range, _, body) = range(&[("Range", "bytes=10-19")]);
+    assert_eq!(status, StatusCode::PartialContent);
+    assert_eq!(content_range, Some(format!("bytes 10-19/{}", len)));
+    assert_eq!(body, &file[10..20]);
+
+    let (_, content_range, _, body) = range(&[("Range", "bytes=-5")]);
+    assert_eq!(content_range, Some(format!("bytes {}-{}/{}", len - 5, len - 1, len)));
+    assert_eq!(body, &file[len - 5..]);
+
+    let (status, content_range, _, body) = range(&[("Range", &format!("bytes={}-", len))]);
+    assert_eq!(status, StatusCode::RangeNotSatisfiable);
+    assert_eq!(content_range, Some(format!("bytes */{}", len)));
+    assert!(body.is_empty());
+
+    // Overlapping ranges are merged
+    let (status, content_range, _, body) = range(&[("Range", "bytes=0-9,5-14")]);
+    assert_eq!(status, StatusCode::PartialContent);
+    assert_eq!(content_range, Some(format!("bytes 0-14/{}", len)));
+    assert_eq!(body, &file[..15]);
+
+    let (status, content_range, content_type, body) = range(&[("Range", "bytes=0-4,-3")]);
+    assert_eq!(status, StatusCode::PartialContent);
+    assert_eq!(content_range, None);
+    let content_type = content_type.unwrap();
+    let boundary = content_type.split("boundary=").nth(1).unwrap();
+    assert!(content_type.starts_with("multipart/byteranges"));
+    let mut expected = format!("\r\n--{b}\r\nContent-Type: application/octet-stream\r\n\
+                                Content-Range: bytes 0-4/{len}\r\n\r\n", b = boundary, len = len)
+        .into_bytes();
+    expected.extend_from_slice(&file[..5]);
+    expected.extend_from_slice(format!("\r\n--{b}\r\nContent-Type: application/octet-stream\r\n\
+                                        Content-Range: bytes {first}-{last}/{len}\r\n\r\n",
+                                       b = boundary, first = len - 3, last = len - 1, len = len)
+                               .as_bytes());
+    expected.extend_from_slice(&file[len - 3..]);
+    expected.extend_from_slice(format!("\r\n--{}--\r\n", boundary).as_bytes());
+    assert_eq!(body, expected);
+
+    // If-Range must match the current validators, or the whole file is sent
+    let data = call(&htdocs, Method::Get, "/Cargo.lock");
+    let etag = data.response.headers().get::<ETag>().unwrap().to_string();
+    let modified = data.response.headers().get::<LastModified>().unwrap().to_string();
+    assert_eq!(range(&[("Range", "bytes=0-4"), ("If-Range", &etag)]).0,
+               StatusCode::PartialContent);
+    assert_eq!(range(&[("Range", "bytes=0-4"), ("If-Range", &modified)]).0,
+               StatusCode::PartialContent);
+    let (status, _, _, body) = range(&[("Range", "bytes=0-4"), ("If-Range", "\"stale\"")]);
+    assert_eq!(status, StatusCode::Ok);
+    assert_eq!(body, file);
+}
+
+#[test]
+fn autoindex()
+{
+    let docroot = tempfile::tempdir().unwrap();
+    fs::create_dir(docroot.path().join("sub dir")).unwrap();
+    fs::write(docroot.path().join("<b>&.txt"), "bold").unwrap();
+    fs::write(docroot.path().join(".secret"), "hidden").unwrap();
+    fs::write(docroot.path().join("sub dir/index.html"), "<p>Index</p>").unwrap();
+
+    let mut htdocs = Htdocs::new(docroot.path(), Some("index.html".to_owned()));
+    assert_eq!(call(&htdocs, Method::Get, "/").response.status(), StatusCode::NotFound);
+
+    htdocs.set_autoindex(Some(Autoindex::default()));
+    let data = call(&htdocs, Method::Get, "/");
+    assert_eq!(data.response.status(), StatusCode::Ok);
+    assert_eq!(data.response.headers().get::<ContentType>(), Some(&ContentType(mime::TEXT_HTML_UTF_8)));
+    let page = text(data);
+    assert!(page.contains("<a href=\"./sub%20dir/\">sub dir/</a>"));
+    assert!(page.contains("<a href=\"./%3Cb%3E&.txt\">&lt;b&gt;&amp;.txt</a></td><td>4</td>"));
+    assert!(page.find("sub dir/").unwrap() < page.find("&lt;b&gt;").unwrap());
+    assert!(!page.contains("secret"));
+    assert!(!page.contains("../"));
+
+    // Directories with an index file are still served by it
+    assert_eq!(body(call(&htdocs, Method::Get, "/sub%20dir/")), b"<p>Index</p>");
+
+    // Directories are listed with a trailing slash
+    fs::create_dir(docroot.path().join("empty")).unwrap();
+    let data = call(&htdocs, Method::Get, "/empty?sort=name");
+    assert_eq!(data.response.status(), StatusCode::MovedPermanently);
+    assert_eq!(data.response.headers().get::<Location>().map(|l| &**l),
+               Some("/empty/?sort=name"));
+    assert!(text(call(&htdocs, Method::Get, "/empty/"))
+            .contains("<a href=\"../\">../</a>"));
+
+    // JSON, for those who prefer it
+    let json = [("Accept", "application/json")];
+    let data = call_with(&htdocs, Method::Get, "/", &json);
+    assert_eq!(data.response.headers().get::<ContentType>(), Some(&ContentType(mime::TEXT_HTML_UTF_8)));
+    htdocs.set_autoindex(Some(Autoindex { json: true, show_hidden: true }));
+    let data = call_with(&htdocs, Method::Get, "/", &json);
+    assert_eq!(data.response.headers().get::<ContentType>(), Some(&ContentType::json()));
+    let listing = text(data);
+    assert!(listing.starts_with("[{\"name\":\"empty\",\"type\":\"directory\","));
+    assert!(listing.contains("{\"name\":\"<b>&.txt\",\"type\":\"file\",\"size\":4,\"modified\":"));
+    assert!(!listing.contains("\".secret\""));
+    htdocs.set_access(Access { deny_hidden: false, ..Access::default() });
+    let listing = text(call_with(&htdocs, Method::Get, "/", &json));
+    assert!(listing.contains("\".secret\""));
+    let data = call_with(&htdocs, Method::Get, "/", &[("Accept", "text/html, application/json;q=0.9")]);
+    assert_eq!(data.response.headers().get::<ContentType>(), Some(&ContentType(mime::TEXT_HTML_UTF_8)));
+}
+
+#[test]
+fn precompressed()
+{
+    let docroot = tempfile::tempdir().unwrap();
+    fs::write(docroot.path().join("app.js"), "plain").unwrap();
+    fs::write(docroot.path().join("app.js.gz"), "gzipped").unwrap();
+    fs::write(docroot.path().join("app.js.br"), "brotli").unwrap();
+    fs::write(docroot.path().join("style.css"), "plain").unwrap();
+    fs::write(docroot.path().join("style.css.gz"), "gzipped").unwrap();
+
+    let mut htdocs = Htdocs::new(docroot.path(), None);
+    let get = |htdocs: &Htdocs, path: &str, accept: &str| {
+        let data = call_with(htdocs, Method::Get, path, &[("Accept-Encoding", accept)]);
+        let encoding = data.response.headers().get::<ContentEncoding>().map(|e| e.0.clone());
+        let content_type = data.response.headers().get::<ContentType>().map(|c| c.to_string());
+        let vary = data.response.headers().get_raw("Vary").is_some();
+        let etag = data.response.headers().get::<ETag>().map(|e| e.to_string());
+        (text(data), encoding, content_type, vary, etag)
+    };
+
+    // Off by default
+    assert_eq!(get(&htdocs, "/app.js", "br, gzip").0, "plain");
+
+    htdocs.set_precompressed(true);
+    let (body, encoding, content_type, vary, etag) = get(&htdocs, "/app.js", "gzip, br");
+    assert_eq!(body, "brotli");
+    assert_eq!(encoding, Some(vec![Encoding::Brotli]));
+    assert_eq!(content_type, Some("text/javascript; charset=utf-8".to_owned()));
+    assert!(vary);
+    let (_, _, _, _, plain_etag) = get(&htdocs, "/app.js", "identity");
+    assert_ne!(etag, plain_etag);
+
+    let (body, encoding, ..) = get(&htdocs, "/app.js", "br;q=0.5, gzip");
+    assert_eq!((&*body, encoding), ("gzipped", Some(vec![Encoding::Gzip])));
+    assert_eq!(get(&htdocs, "/app.js", "br;q=0, gzip;q=0").0, "plain");
+    assert_eq!(get(&htdocs, "/app.js", "*").0, "brotli");
+    assert_eq!(get(&htdocs, "/app.js", "br;q=0, *").0, "gzipped");
+    assert_eq!(get(&htdocs, "/style.css", "gzip;q=0, *").0, "plain");
+    assert_eq!(get(&htdocs, "/style.css", "br, gzip").0, "gzipped");
+
+    let (body, encoding, _, vary, _) = get(&htdocs, "/style.css", "deflate");
+    assert_eq!((&*body, encoding), ("plain", None));
+    assert!(vary);
+
+    // Request headers already listed in Vary are kept, and not repeated
+    for &(before, after) in &[("Origin", "Origin, Accept-Encoding"),
+                              ("Origin, accept-encoding", "Origin, accept-encoding")] {
+        let mut data = common::data(common::request(Method::Get, "/app.js",
+                                                    &[("Accept-Encoding", "gzip")]));
+        data.response.headers_mut().set_raw("Vary", before);
+        let result: Result<_, IoError> = htdocs.handle(data).wait();
+        let vary = result.unwrap().response.headers().get_raw("Vary").unwrap().clone();
+        assert_eq!(vary, *after);
+    }
+}
+
+#[test]
+fn cache()
+{
+    let docroot = tempfile::tempdir().unwrap();
+    // Rewrite a file without changing its size or modification time, so only a
+    // cached copy would still give the old contents
+    let rewrite = |name: &str, contents: &str| {
+        let path = docroot.path().join(name);
+        let modified = fs::metadata(&path).unwrap().modified().unwrap();
+        fs::write(&path, contents).unwrap();
+        fs::File::options().write(true).open(&path).unwrap().set_modified(modified).unwrap();
+    };
+    for name in &["a.txt", "b.txt", "c.txt"] {
+        fs::write(docroot.path().join(name), "0123456789").unwrap();
+    }
+    fs::write(docroot.path().join("big.txt"), "0123456789abcdef").unwrap();
+
+    let mut htdocs = Htdocs::new(docroot.path(), None);
+    htdocs.set_cache(Some(Cache { max_file_size: 10, max_total_size: 20 }));
+    let get = |htdocs: &Htdocs, path: &str| {
+        text(call(htdocs, Method::Get, path))
+    };
+
+    // Cached files are served from memory
+    assert_eq!(get(&htdocs, "/a.txt"), "0123456789");
+    rewrite("a.txt", "abcdefghij");
+    assert_eq!(get(&htdocs, "/a.txt"), "0123456789");
+
+    // Including ranges of them
+    let data = call_with(&htdocs, Method::Get, "/a.txt", &[("Range", "bytes=2-4")]);
+    assert_eq!(data.response.status(), StatusCode::PartialContent);
+    assert_eq!(body(data), b"234");
+
+    // A change to the modification time invalidates them
+    fs::write(docroot.path().join("a.txt"), "ABCDEFGHIJ").unwrap();
+    fs::File::options().write(true).open(docroot.path().join("a.txt")).unwrap()
+        .set_modified(std::time::SystemTime::now() + std::time::Duration::from_secs(60))
+        .unwrap();
+    assert_eq!(get(&htdocs, "/a.txt"), "ABCDEFGHIJ");
+
+    // Files which are too large are not cached
+    assert_eq!(get(&htdocs, "/big.txt"), "0123456789abcdef");
+    rewrite("big.txt", "fedcba9876543210");
+    assert_eq!(get(&htdocs, "/big.txt"), "fedcba9876543210");
+
+    // The least recently used files are dropped to make room
+    assert_eq!(get(&htdocs, "/b.txt"), "0123456789");
+    assert_eq!(get(&htdocs, "/a.txt"), "ABCDEFGHIJ");
+    assert_eq!(get(&htdocs, "/c.txt"), "0123456789");
+    rewrite("a.txt", "0000000000");
+    rewrite("b.txt", "1111111111");
+    assert_eq!(get(&htdocs, "/a.txt"), "ABCDEFGHIJ");
+    assert_eq!(get(&htdocs, "/b.txt"), "1111111111");
+
+    // Missing files are still passed on
+    assert_eq!(call(&htdocs, Method::Get, "/missing.txt").response.status(),
+               StatusCode::NotFound);
+
+    // A file larger than the whole cache does not empty it
+    htdocs.set_cache(Some(Cache { max_file_size: 16, max_total_size: 10 }));
+    assert_eq!(get(&htdocs, "/c.txt"), "0123456789");
+    assert_eq!(get(&htdocs, "/big.txt"), "fedcba9876543210");
+    rewrite("c.txt", "2222222222");
+    assert_eq!(get(&htdocs, "/c.txt"), "0123456789");
+}
+
+#[test]
+fn access()
+{
+    let outside = tempfile::tempdir().unwrap();
+    fs::write(outside.path().join("passwd"), "root").unwrap();
+    let docroot = tempfile::tempdir().unwrap();
+    fs::write(docroot.path().join("page.html"), "page").unwrap();
+    fs::write(docroot.path().join("page.html.bak"), "old").unwrap();
+    fs::write(docroot.path().join(".env"), "SECRET=1").unwrap();
+    fs::create_dir(docroot.path().join(".git")).unwrap();
+    fs::write(docroot.path().join(".git/config"), "[core]").unwrap();
+    fs::create_dir(docroot.path().join(".well-known")).unwrap();
+    fs::write(docroot.path().join(".well-known/security.txt"), "Contact: x").unwrap();
+    fs::create_dir(docroot.path().join("private")).unwrap();
+    fs::write(docroot.path().join("private/notes.txt"), "notes").unwrap();
+    fs::create_dir(docroot.path().join("private/sub")).unwrap();
+    fs::write(docroot.path().join("private/sub/secret.txt"), "secret").unwrap();
+    std::os::unix::fs::symlink(outside.path(), docroot.path().join("escape")).unwrap();
+    std::os::unix::fs::symlink(docroot.path().join("page.html"), docroot.path().join("alias.html"))
+        .unwrap();
+
+    let mut htdocs = Htdocs::new(docroot.path(), None);
+    let status = |htdocs: &Htdocs, path: &str| call(htdocs, Method::Get, path).response.status();
+
+    // By default, hidden files and links out of the docroot are refused
+    assert_eq!(status(&htdocs, "/page.html"), StatusCode::Ok);
+    assert_eq!(status(&htdocs, "/.env"), StatusCode::NotFound);
+    assert_eq!(status(&htdocs, "/.git/config"), StatusCode::NotFound);
+    assert_eq!(status(&htdocs, "/%2egit/config"), StatusCode::NotFound);
+    assert_eq!(status(&htdocs, "/.well-known/security.txt"), StatusCode::Ok);
+    assert_eq!(status(&htdocs, "/escape/passwd"), StatusCode::NotFound);
+    assert_eq!(status(&htdocs, "/alias.html"), StatusCode::Ok);
+    assert_eq!(status(&htdocs, "/page.html.bak"), StatusCode::Ok);
+
+    htdocs.set_access(Access {
+        symlinks: Symlinks::Deny,
+        deny_hidden: false,
+        deny: vec!["*.bak".to_owned(), "private/*".to_owned()],
+    });
+    assert_eq!(status(&htdocs, "/.env"), StatusCode::Ok);
+    assert_eq!(status(&htdocs, "/alias.html"), StatusCode::NotFound);
+    assert_eq!(status(&htdocs, "/escape/passwd"), StatusCode::NotFound);
+    assert_eq!(status(&htdocs, "/page.html.bak"), StatusCode::NotFound);
+    assert_eq!(status(&htdocs, "/private/notes.txt"), StatusCode::NotFound);
+    assert_eq!(status(&htdocs, "/private/sub/secret.txt"), StatusCode::NotFound);
+    assert_eq!(status(&htdocs, "/page.html"), StatusCode::Ok);
+
+    htdocs.set_access(Access { symlinks: Symlinks::Follow, ..Access::default() });
+    assert_eq!(body(call(&htdocs, Method::Get, "/escape/passwd")), b"root");
+
+    // Refused entries are left out of listings
+    htdocs.set_access(Access::default());
+    htdocs.set_autoindex(Some(Autoindex { json: false, show_hidden: true }));
+    let listing = text(call(&htdocs, Method::Get, "/"));
+    assert!(listing.contains(".well-known"));
+    assert!(!listing.contains(".git"));
+    assert!(!listing.contains("escape"));
+    assert_eq!(status(&htdocs, "/.git/"), StatusCode::NotFound);
+}
+
+#[test]
+fn memory_source()
+{
+    static ASSETS: &[(&str, &[u8])] = &[
+        ("index.html", b"<h1>Home</h1>"),
+        ("css/site.css", b"body { color: red }"),
+        ("css/print.css", b"@media print {}"),
+    ];
+    let mut htdocs = Htdocs::with_source(Memory::from_manifest(ASSETS),
+                                         Some("index.html".to_owned()));
+
+    // Files, their types, and directory indexes
+    let data = call(&htdocs, Method::Get, "/css/site.css");
+    assert_eq!(data.response.status(), StatusCode::Ok);
+    assert_eq!(data.response.headers().get::<ContentType>(),
+               Some(&ContentType("text/css; charset=utf-8".parse().unwrap())));
+    assert_eq!(data.response.headers().get::<ContentLength>(), Some(&ContentLength(19)));
+    let etag = data.response.headers().get::<ETag>().unwrap().clone();
+    assert!(data.response.headers().has::<LastModified>());
+    assert_eq!(body(data), b"body { color: red }");
+    assert_eq!(body(call(&htdocs, Method::Get, "/")), b"<h1>Home</h1>");
+    assert_eq!(call(&htdocs, Method::Get, "/missing.css").response.status(),
+               StatusCode::NotFound);
+    assert_eq!(call(&htdocs, Method::Get, "/css").response.status(), StatusCode::NotFound);
+
+    // Conditional requests
+    let data = call_with(&htdocs, Method::Get, "/css/site.css",
+                         &[("If-None-Match", &etag.to_string())]);
+    assert_eq!(data.response.status(), StatusCode::NotModified);
+
+    // Ranges
+    let data = call_with(&htdocs, Method::Get, "/css/site.css", &[("Range", "bytes=0-3")]);
+    assert_eq!(data.response.status(), StatusCode::PartialContent);
+    assert_eq!(body(data), b"body");
+    let data = call_with(&htdocs, Method::Get, "/css/site.css", &[("Range", "bytes=0-0,-3")]);
+    let parts = text(data);
+    assert!(parts.contains("Content-Range: bytes 0-0/19\r\n\r\nb\r\n"));
+    assert!(parts.contains("Content-Range: bytes 16-18/19\r\n\r\nd }\r\n"));
+
+    // Listings of implied directories
+    htdocs.set_autoindex(Some(Autoindex { json: true, show_hidden: false }));
+    let data = call_with(&htdocs, Method::Get, "/css/", &[("Accept", "application/json")]);
+    let listing = text(data);
+    assert!(listing.starts_with("[{\"name\":\"print.css\",\"type\":\"file\",\"size\":15,"));
+    assert!(listing.contains("{\"name\":\"site.css\",\"type\":\"file\",\"size\":19,"));
+}
+
+#[test]
+fn fallback()
+{
+    static ASSETS: &[(&str, &[u8])] = &[
+        ("index.html", b"<div id=app></div>"),
+        ("app.js", b"start()"),
+    ];
+    let mut htdocs = Htdocs::with_source(Memory::from_manifest(ASSETS), None);
+    let html = [("Accept", "text/html,application/xhtml+xml,*/*;q=0.8")];
+    let get = |htdocs: &Htdocs, path: &str, headers: &[(&str, &str)]| {
+        let data = call_with(htdocs, Method::Get, path, headers);
+        (data.response.status(), text(data))
+    };
+
+    assert_eq!(get(&htdocs, "/app/settings/profile", &html).0, StatusCode::NotFound);
+
+    let mut fallback = Fallback::new("index.html");
+    fallback.exclude.push("/api/".to_owned());
+    htdocs.set_fallback(Some(fallback));
+
+    // Paths which match no file get the fallback, if the client wants HTML
+    assert_eq!(get(&htdocs, "/app/settings/profile", &html),
+               (StatusCode::Ok, "<div id=app></div>".to_owned()));
+    assert_eq!(get(&htdocs, "/", &html), (StatusCode::Ok, "<div id=app></div>".to_owned()));
+    let data = call_with(&htdocs, Method::Get, "/app/settings", &html);
+    assert_eq!(data.response.headers().get::<ContentType>(),
+               Some(&ContentType(mime::TEXT_HTML_UTF_8)));
+    assert_eq!(get(&htdocs, "/app/settings", &[("Accept", "application/json")]).0,
+               StatusCode::NotFound);
+    assert_eq!(get(&htdocs, "/app/settings", &[("Accept", "*/*")]).0, StatusCode::NotFound);
+    assert_eq!(get(&htdocs, "/app/settings", &[]).0, StatusCode::NotFound);
+
+    // Files which exist are served as usual
+    assert_eq!(get(&htdocs, "/app.js", &html), (StatusCode::Ok, "start()".to_owned()));
+
+    // But missing assets and excluded prefixes are not
+    assert_eq!(get(&htdocs, "/missing.js", &html).0, StatusCode::NotFound);
+    assert_eq!(get(&htdocs, "/api/users", &html).0, StatusCode::NotFound);
+    assert_eq!(get(&htdocs, "/api", &html).0, StatusCode::NotFound);
+    assert_eq!(get(&htdocs, "/apiary", &html).0, StatusCode::Ok);
+
+    // A missing fallback file is passed on too
+    htdocs.set_fallback(Some(Fallback::new("missing.html")));
+    assert_eq!(get(&htdocs, "/app/settings", &html).0, StatusCode::NotFound);
+}
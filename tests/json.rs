@@ -0,0 +1,89 @@
+#![cfg(feature = "json")]
+
+extern crate pemmican;
+extern crate hyper;
+extern crate futures;
+extern crate serde_json;
+#[macro_use] extern crate serde;
+
+mod common;
+
+use std::collections::HashMap;
+use futures::Future;
+use hyper::{Method, StatusCode};
+use hyper::header::{ContentLength, ContentType};
+use hyper::server::Response;
+use pemmican::PluginData;
+use pemmican::forms::Limits;
+
+#[derive(Serialize, Deserialize, Debug, PartialEq)]
+struct User {
+    name: String,
+    age: u32,
+}
+
+fn request(content_type: &str, body: &'static str) -> PluginData<()>
+{
+    let mut request = common::request(Method::Post, "/users", &[("Content-Type", content_type)]);
+    request.set_body(body);
+    common::data(request)
+}
+
+fn body(response: Response) -> serde_json::Value
+{
+    serde_json::from_slice(&common::response_body(response)).unwrap()
+}
+
+#[test]
+fn json_body()
+{
+    let mut data = request("application/json", r#"{"name": "Mike", "age": 42}"#);
+    assert_eq!(data.json_body::<User>(&Limits::default()).wait(),
+               Ok(User { name: "Mike".to_owned(), age: 42 }));
+
+    let mut data = request("application/vnd.api+json", r#"{"name": "Mike", "age": 42}"#);
+    assert!(data.json_body::<User>(&Limits::default()).wait().is_ok());
+
+    let failures = [
+        ("application/json", r#"{"name": "Mike", "#, StatusCode::BadRequest),
+        ("application/json", r#"{"name": "Mike", "age": "old"}"#, StatusCode::UnprocessableEntity),
+        ("text/plain", r#"{"name": "Mike", "age": 42}"#, StatusCode::UnsupportedMediaType),
+    ];
+    for &(content_type, json, status) in &failures {
+        let mut data = request(content_type, json);
+        let error = data.json_body::<User>(&Limits::default()).wait().unwrap_err();
+        assert_eq!(error.status(), status);
+
+        let response = error.response();
+        assert_eq!(response.headers().get::<ContentType>(), Some(&ContentType::json()));
+        assert!(body(response)["error"].is_string());
+    }
+
+    // Bodies larger than the limit are not read
+    let mut data = request("application/json", r#"{"name": "Mike", "age": 42}"#);
+    let limits = Limits { max_total_size: 16, ..Limits::default() };
+    let error = data.json_body::<User>(&limits).wait().unwrap_err();
+    assert_eq!(error.status(), StatusCode::PayloadTooLarge);
+    let response = error.response();
+    assert_eq!(response.headers().get::<ContentType>(), Some(&ContentType::json()));
+    assert!(body(response)["error"].is_string());
+}
+
+#[test]
+fn respond_json()
+{
+    let mut data = request("application/json", "");
+    data.respond_json(StatusCode::Created, &User { name: "Mike".to_owned(), age: 42 });
+    assert_eq!(data.response.status(), StatusCode::Created);
+    assert_eq!(data.response.headers().get::<ContentType>(), Some(&ContentType::json()));
+    assert_eq!(data.response.headers().get::<ContentLength>(), Some(&ContentLength(24)));
+    assert_eq!(body(data.response), serde_json::json!({ "name": "Mike", "age": 42 }));
+
+    // Maps must have string keys
+    let mut data = request("application/json", "");
+    let mut unserializable = HashMap::new();
+    unserializable.insert((1, 2), "pair");
+    data.respond_json(StatusCode::Ok, &unserializable);
+    assert_eq!(data.response.status(), StatusCode::InternalServerError);
+    assert!(body(data.response)["error"].is_string());
+}
@@ -0,0 +1,40 @@
+extern crate pemmican;
+extern crate hyper;
+
+use std::path::Path;
+use hyper::mime;
+use pemmican::mime_types::{guess, MimeTypes};
+
+#[test]
+fn table()
+{
+    let types = MimeTypes::new();
+    let get = |path: &str| types.get(Path::new(path)).map(|m| m.to_string());
+    assert_eq!(get("index.html"), Some("text/html; charset=utf-8".to_owned()));
+    assert_eq!(get("site.CSS"), Some("text/css; charset=utf-8".to_owned()));
+    assert_eq!(get("app.js"), Some("text/javascript; charset=utf-8".to_owned()));
+    assert_eq!(get("logo.svg"), Some("image/svg+xml".to_owned()));
+    assert_eq!(get("font.woff2"), Some("font/woff2".to_owned()));
+    assert_eq!(get("archive.tar.gz"), Some("application/gzip".to_owned()));
+    assert_eq!(get("Makefile"), Some("application/octet-stream".to_owned()));
+    assert_eq!(get("data.unknown"), Some("application/octet-stream".to_owned()));
+
+    assert_eq!(guess(Path::new("a/b/photo.JPG")), mime::IMAGE_JPEG);
+}
+
+#[test]
+fn extending()
+{
+    let mut types = MimeTypes::new();
+    types.insert("TOML", "application/toml".parse().unwrap());
+    types.insert("js", mime::APPLICATION_JAVASCRIPT_UTF_8);
+    types.set_default(Some(mime::TEXT_PLAIN_UTF_8));
+    assert_eq!(types.get(Path::new("Cargo.toml")).map(|m| m.to_string()),
+               Some("application/toml".to_owned()));
+    assert_eq!(types.get(Path::new("app.js")), Some(&mime::APPLICATION_JAVASCRIPT_UTF_8));
+    assert_eq!(types.get(Path::new("LICENSE")), Some(&mime::TEXT_PLAIN_UTF_8));
+
+    let mut types = MimeTypes::empty();
+    types.set_default(None);
+    assert_eq!(types.get(Path::new("index.html")), None);
+}
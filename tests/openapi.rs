@@ -0,0 +1,73 @@
+#![cfg(feature = "openapi")]
+
+extern crate pemmican;
+extern crate hyper;
+extern crate futures;
+extern crate serde_json;
+
+mod common;
+
+use std::io::Error as IoError;
+use futures::Future;
+use hyper::{Method, StatusCode};
+use hyper::header::ContentType;
+use pemmican::PluginData;
+use pemmican::plugins::Router;
+use pemmican::plugins::router::openapi::OpenApi;
+
+fn handler(data: PluginData<()>)
+           -> Box<dyn Future<Item = PluginData<()>, Error = IoError>>
+{
+    Box::new(futures::future::ok( data ))
+}
+
+#[test]
+fn openapi_document()
+{
+    let router: Router<(), IoError> = Router::new();
+    router.route("/users/:id", Method::Get)
+        .name("getUser").summary("Show a user").tag("users")
+        .to(handler);
+    router.insert("/users/:id", Method::Delete, handler);
+    router.insert("/static/**", Method::Get, handler);
+
+    let api = OpenApi::new("Example", "1.0");
+    let doc = api.document(&router.routes());
+    assert_eq!(doc, serde_json::json!({
+        "openapi": "3.0.3",
+        "info": { "title": "Example", "version": "1.0" },
+        "paths": {
+            "/users/{id}": {
+                "get": {
+                    "operationId": "getUser",
+                    "summary": "Show a user",
+                    "tags": ["users"],
+                    "parameters": [{
+                        "name": "id",
+                        "in": "path",
+                        "required": true,
+                        "schema": { "type": "string" }
+                    }],
+                    "responses": { "default": { "description": "Response" } }
+                },
+                "delete": {
+                    "parameters": [{
+                        "name": "id",
+                        "in": "path",
+                        "required": true,
+                        "schema": { "type": "string" }
+                    }],
+                    "responses": { "default": { "description": "Response" } }
+                }
+            }
+        }
+    }));
+
+    router.insert("/openapi.json", Method::Get, api.handler(&router.routes()));
+
+    let data = common::call(&router, Method::Get, "/openapi.json");
+    assert_eq!(data.response.status(), StatusCode::Ok);
+    assert_eq!(data.response.headers().get::<ContentType>(), Some(&ContentType::json()));
+    let served: serde_json::Value = serde_json::from_slice(&common::body(data)).unwrap();
+    assert_eq!(served, doc);
+}
@@ -0,0 +1,34 @@
+extern crate pemmican;
+
+use pemmican::path::{normalize, encode, toggle_trailing_slash};
+
+#[test]
+fn normalization()
+{
+    assert_eq!(normalize("/users/mike").unwrap(), "/users/mike");
+    assert_eq!(normalize("/").unwrap(), "/");
+    assert_eq!(normalize("/my%20file.txt").unwrap(), "/my file.txt");
+    assert_eq!(normalize("/caf%C3%A9").unwrap(), "/café");
+    assert_eq!(normalize("//users///mike/").unwrap(), "/users/mike/");
+    assert_eq!(normalize("/a/./b/../c").unwrap(), "/a/c");
+    assert_eq!(normalize("/a/b/..").unwrap(), "/a/");
+    assert_eq!(normalize("/../../etc/passwd").unwrap(), "/etc/passwd");
+    assert_eq!(normalize("/%2e%2e/etc/passwd").unwrap(), "/etc/passwd");
+
+    // Unsafe or malformed escapes
+    assert_eq!(normalize("/a%2Fb"), None);
+    assert_eq!(normalize("/a%00b"), None);
+    assert_eq!(normalize("/a%zzb"), None);
+    assert_eq!(normalize("/a%2"), None);
+    assert_eq!(normalize("/a%FFb"), None);
+}
+
+#[test]
+fn encoding()
+{
+    assert_eq!(encode("/my file.txt"), "/my%20file.txt");
+    assert_eq!(encode("/100%/done?"), "/100%25/done%3F");
+    assert_eq!(toggle_trailing_slash("/about"), Some("/about/".to_owned()));
+    assert_eq!(toggle_trailing_slash("/about/"), Some("/about".to_owned()));
+    assert_eq!(toggle_trailing_slash("/"), None);
+}
@@ -0,0 +1,76 @@
+extern crate pemmican;
+extern crate hyper;
+extern crate futures;
+extern crate tokio_service;
+#[cfg(feature = "serde")] #[macro_use] extern crate serde;
+
+mod common;
+
+use std::io::Error as IoError;
+use std::sync::Arc;
+use futures::Future;
+use hyper::{Method, StatusCode};
+use hyper::server::Request;
+use tokio_service::Service;
+use pemmican::{Pemmican, Config, PluginData, Plugin, Query, HttpError};
+
+fn data(uri: &str) -> PluginData<()>
+{
+    common::data(common::request(Method::Get, uri, &[]))
+}
+
+#[test]
+fn parsing()
+{
+    let query = data("/search?q=fish+%26+chips&tag=a&tag=b%20c&empty=&flag").query();
+    assert_eq!(query.get("q"), Some("fish & chips"));
+    assert_eq!(query.get_all("tag").collect::<Vec<_>>(), vec!["a", "b c"]);
+    assert_eq!(query.get("empty"), Some(""));
+    assert_eq!(query.get("flag"), Some(""));
+    assert!(!query.contains("missing"));
+    assert_eq!(query.len(), 5);
+
+    assert!(data("/search").query().is_empty());
+    assert_eq!(Query::parse(b"caf%C3%A9=%FF").iter().collect::<Vec<_>>(),
+               vec![("café", "\u{FFFD}")]);
+}
+
+struct Fails;
+impl Plugin<(),IoError> for Fails {
+    fn handle(&self, _data: PluginData<()>)
+              -> Box<dyn Future<Item = PluginData<()>, Error = IoError>>
+    {
+        Box::new(futures::future::err(HttpError::bad_request("No page given").into()))
+    }
+}
+
+#[test]
+fn http_errors()
+{
+    let pemmican = Pemmican::new(Config::default(), vec![Arc::new(Box::new(Fails))], ());
+    let response = pemmican.call(Request::new(Method::Get, "/".parse().unwrap()))
+        .wait().unwrap();
+    assert_eq!(response.status(), StatusCode::BadRequest);
+    assert_eq!(common::response_body(response), b"No page given");
+}
+
+#[cfg(feature = "serde")]
+#[derive(Deserialize, Debug, PartialEq)]
+struct Search {
+    q: String,
+    page: Option<u32>,
+}
+
+#[cfg(feature = "serde")]
+#[test]
+fn deserializing()
+{
+    assert_eq!(data("/search?q=fish+cakes&page=2").query_as::<Search>(),
+               Ok(Search { q: "fish cakes".to_owned(), page: Some(2) }));
+    assert_eq!(data("/search?q=fish").query_as::<Search>(),
+               Ok(Search { q: "fish".to_owned(), page: None }));
+
+    let error = data("/search?q=fish&page=two").query_as::<Search>().unwrap_err();
+    assert_eq!(error.status(), StatusCode::BadRequest);
+    assert!(data("/search").query_as::<Search>().is_err());
+}
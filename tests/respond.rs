@@ -0,0 +1,79 @@
+extern crate pemmican;
+extern crate hyper;
+extern crate futures;
+extern crate tempfile;
+
+mod common;
+
+use std::io::{Error as IoError, Write};
+use futures::Future;
+use hyper::{Method, StatusCode};
+use hyper::header::{ContentLength, ContentType, Location, SetCookie};
+use pemmican::PluginData;
+use common::text;
+
+fn request() -> PluginData<()>
+{
+    common::data(common::request(Method::Get, "/", &[]))
+}
+
+#[test]
+fn helpers()
+{
+    let mut data = request();
+    data.response.headers_mut().set(SetCookie(vec!["a=b".to_owned()]));
+    data.html("<p>Hi</p>");
+    assert_eq!(data.response.status(), StatusCode::Ok);
+    assert_eq!(data.response.headers().get::<ContentType>(), Some(&ContentType::html()));
+    assert_eq!(data.response.headers().get::<ContentLength>(), Some(&ContentLength(9)));
+    assert!(data.response.headers().has::<SetCookie>());
+    assert_eq!(text(data), "<p>Hi</p>");
+
+    let mut data = request();
+    data.text("Hello".to_owned());
+    assert_eq!(data.response.headers().get::<ContentType>(), Some(&ContentType::plaintext()));
+    assert_eq!(text(data), "Hello");
+
+    let mut data = request();
+    data.text("Replaced");
+    data.redirect(StatusCode::SeeOther, "/elsewhere");
+    assert_eq!(data.response.status(), StatusCode::SeeOther);
+    assert_eq!(data.response.headers().get::<Location>().map(|l| &**l), Some("/elsewhere"));
+    assert_eq!(data.response.headers().get::<ContentLength>(), Some(&ContentLength(0)));
+    assert!(!data.response.headers().has::<ContentType>());
+    assert_eq!(text(data), "");
+
+    let mut data = request();
+    data.not_found();
+    assert_eq!(data.response.status(), StatusCode::NotFound);
+    assert_eq!(text(data), "Not Found");
+
+    let mut data = request();
+    data.text("Replaced");
+    data.no_content();
+    assert_eq!(data.response.status(), StatusCode::NoContent);
+    assert!(!data.response.headers().has::<ContentLength>());
+    assert_eq!(text(data), "");
+}
+
+#[test]
+fn files()
+{
+    let mut css = tempfile::Builder::new().suffix(".css").tempfile().unwrap();
+    css.write_all(b"p { color: red }").unwrap();
+    let data = request().file::<_, IoError>(css.path()).wait().unwrap();
+    assert_eq!(data.response.status(), StatusCode::Ok);
+    assert_eq!(data.response.headers().get::<ContentType>().map(|c| c.to_string()),
+               Some("text/css; charset=utf-8".to_owned()));
+    assert_eq!(data.response.headers().get::<ContentLength>(), Some(&ContentLength(16)));
+    assert_eq!(text(data), "p { color: red }");
+
+    let data = request().file::<_, IoError>("Cargo.toml").wait().unwrap();
+    assert_eq!(data.response.headers().get::<ContentType>().map(|c| c.to_string()),
+               Some("application/octet-stream".to_owned()));
+
+    let data = request().file::<_, IoError>("no/such/file.html").wait().unwrap();
+    assert_eq!(data.response.status(), StatusCode::NotFound);
+    let data = request().file::<_, IoError>("src").wait().unwrap();
+    assert_eq!(data.response.status(), StatusCode::NotFound);
+}
@@ -0,0 +1,503 @@
+extern crate pemmican;
+extern crate hyper;
+extern crate futures;
+
+mod common;
+
+use std::io::Error as IoError;
+use std::sync::Arc;
+use futures::Future;
+use hyper::{Method, StatusCode};
+use hyper::header::{Allow, ContentLength, Location};
+use pemmican::{PluginData, Plugin};
+use pemmican::path::TrailingSlash;
+use pemmican::plugins::{Router, Chain, PageVisits};
+use pemmican::plugins::router::{UrlError, guard};
+use common::{body, call, call_with, text};
+
+fn by_name(mut data: PluginData<()>)
+           -> Box<dyn Future<Item = PluginData<()>, Error = IoError>>
+{
+    let body = format!("static {}", data.params.len());
+    data.response.set_body(body);
+    data.response.set_status(StatusCode::Ok);
+    Box::new(futures::future::ok( data ))
+}
+
+fn by_param(mut data: PluginData<()>)
+            -> Box<dyn Future<Item = PluginData<()>, Error = IoError>>
+{
+    let body = format!("param {}", data.params["name"]);
+    data.response.set_body(body);
+    data.response.set_status(StatusCode::Ok);
+    Box::new(futures::future::ok( data ))
+}
+
+fn by_wildcard(mut data: PluginData<()>)
+               -> Box<dyn Future<Item = PluginData<()>, Error = IoError>>
+{
+    let body = format!("wildcard {}", data.params["rest"]);
+    data.response.set_body(body);
+    data.response.set_status(StatusCode::Ok);
+    Box::new(futures::future::ok( data ))
+}
+
+fn by_prefix(mut data: PluginData<()>)
+             -> Box<dyn Future<Item = PluginData<()>, Error = IoError>>
+{
+    data.response.set_body("prefix");
+    data.response.set_status(StatusCode::Ok);
+    Box::new(futures::future::ok( data ))
+}
+
+// Run a GET request with extra headers through the router, returning the body
+fn get_with(router: &Router<(), IoError>, path: &str, headers: &[(&str, &str)]) -> String
+{
+    text(call_with(router, Method::Get, path, headers))
+}
+
+// Run a GET request through the router, returning the status and body
+fn get(router: &Router<(), IoError>, path: &str) -> (StatusCode, String)
+{
+    let data = call(router, Method::Get, path);
+    (data.response.status(), text(data))
+}
+
+#[test]
+fn precedence()
+{
+    let router = Router::new();
+    router.insert("/users/admin", Method::Get, by_name);
+    router.insert("/users/:name", Method::Get, by_param);
+    router.insert("/users/*rest", Method::Get, by_wildcard);
+    router.insert("/static/**", Method::Get, by_prefix);
+
+    assert_eq!(get(&router, "/users/admin"), (StatusCode::Ok, "static 0".to_owned()));
+    assert_eq!(get(&router, "/users/mike"), (StatusCode::Ok, "param mike".to_owned()));
+    assert_eq!(get(&router, "/users/mike/photos"),
+               (StatusCode::Ok, "wildcard mike/photos".to_owned()));
+    assert_eq!(get(&router, "/users/"), (StatusCode::Ok, "wildcard ".to_owned()));
+    assert_eq!(get(&router, "/static"), (StatusCode::Ok, "prefix".to_owned()));
+    assert_eq!(get(&router, "/static/css/site.css"), (StatusCode::Ok, "prefix".to_owned()));
+    assert_eq!(get(&router, "/other").0, StatusCode::NotFound);
+
+    router.remove("/users/:name", Method::Get);
+    assert_eq!(get(&router, "/users/mike"), (StatusCode::Ok, "wildcard mike".to_owned()));
+
+    // A bare `*` is anonymous, as `**` is, so captures nothing
+    router.insert("/files/*", Method::Get, |mut data: PluginData<()>| {
+        let body = format!("{} params", data.params.len());
+        data.response.set_body(body);
+        data.response.set_status(StatusCode::Ok);
+        Box::new(futures::future::ok( data )) as Box<dyn Future<Item = _, Error = _>>
+    });
+    assert_eq!(get(&router, "/files/a/b"), (StatusCode::Ok, "0 params".to_owned()));
+}
+
+#[test]
+fn leftmost_segment_decides()
+{
+    let router = Router::new();
+    router.insert("/:name/profile", Method::Get, by_param);
+    router.insert("/users/*rest", Method::Get, by_wildcard);
+
+    assert_eq!(get(&router, "/users/profile"),
+               (StatusCode::Ok, "wildcard profile".to_owned()));
+    assert_eq!(get(&router, "/mike/profile"), (StatusCode::Ok, "param mike".to_owned()));
+}
+
+#[test]
+fn closure_handlers()
+{
+    let greeting = Arc::new("Hello".to_owned());
+
+    let router = Router::new();
+    router.insert("/greet/:name", Method::Get, move |mut data: PluginData<()>| {
+        let body = format!("{} {}", greeting, data.params["name"]);
+        data.response.set_body(body);
+        data.response.set_status(StatusCode::Ok);
+        Box::new(futures::future::ok( data )) as Box<dyn Future<Item = _, Error = _>>
+    });
+
+    assert_eq!(get(&router, "/greet/mike"), (StatusCode::Ok, "Hello mike".to_owned()));
+}
+
+#[test]
+fn plugin_handlers()
+{
+    let inner = Router::new();
+    inner.insert("/admin/users", Method::Get, by_name);
+
+    let chain: Chain<(), IoError> = Chain::new(vec![
+        Arc::new(Box::new(inner)),
+        Arc::new(Box::new(PageVisits::new())),
+    ]);
+
+    let router = Router::new();
+    router.insert("/admin/**", Method::Get, chain);
+
+    assert_eq!(get(&router, "/admin/users"), (StatusCode::Ok, "static 0".to_owned()));
+    assert_eq!(get(&router, "/admin/groups").0, StatusCode::NotFound);
+}
+
+#[test]
+fn method_not_allowed()
+{
+    let router = Router::new();
+    router.insert("/users/:name", Method::Get, by_param);
+    router.insert("/users/admin", Method::Delete, by_name);
+
+    let data = call(&router, Method::Post, "/users/admin");
+    assert_eq!(data.response.status(), StatusCode::MethodNotAllowed);
+    assert_eq!(data.response.headers().get::<Allow>(),
+               Some(&Allow(vec![Method::Delete, Method::Get, Method::Head, Method::Options])));
+
+    let data = call(&router, Method::Post, "/users/mike");
+    assert_eq!(data.response.headers().get::<Allow>(),
+               Some(&Allow(vec![Method::Get, Method::Head, Method::Options])));
+
+    let data = call(&router, Method::Post, "/groups");
+    assert_eq!(data.response.status(), StatusCode::NotFound);
+    assert_eq!(data.response.headers().get::<Allow>(), None);
+
+    // Including beside a mounted router, when it handles nothing
+    let api = Router::new();
+    api.insert("/x", Method::Get, by_name);
+    let router = Router::new();
+    router.mount("/api", api);
+    router.insert("/api/y", Method::Post, by_name);
+    let data = call(&router, Method::Get, "/api/y");
+    assert_eq!(data.response.status(), StatusCode::MethodNotAllowed);
+    assert_eq!(data.response.headers().get::<Allow>(),
+               Some(&Allow(vec![Method::Post, Method::Options])));
+    assert_eq!(call(&router, Method::Options, "/api/y").response.status(), StatusCode::Ok);
+    assert_eq!(get(&router, "/api/x").0, StatusCode::Ok);
+    assert_eq!(get(&router, "/api/z").0, StatusCode::NotFound);
+}
+
+#[test]
+fn options()
+{
+    let router = Router::new();
+    router.insert("/users/:name", Method::Get, by_param);
+    router.insert("/users/:name", Method::Put, by_param);
+    router.insert("/groups", Method::Options, by_name);
+
+    let data = call(&router, Method::Options, "/users/mike");
+    assert_eq!(data.response.status(), StatusCode::Ok);
+    assert_eq!(data.response.headers().get::<Allow>(),
+               Some(&Allow(vec![Method::Get, Method::Head, Method::Put, Method::Options])));
+
+    // An explicit OPTIONS route takes over
+    let data = call(&router, Method::Options, "/groups");
+    assert_eq!(data.response.status(), StatusCode::Ok);
+    assert_eq!(data.response.headers().get::<Allow>(), None);
+}
+
+#[test]
+fn head_runs_get()
+{
+    let router = Router::new();
+    router.insert("/users/:name", Method::Get, by_param);
+    router.insert("/sized", Method::Get, |mut data: PluginData<()>| {
+        data.response.headers_mut().set(ContentLength(100));
+        data.response.set_body("not really 100 bytes");
+        data.response.set_status(StatusCode::Ok);
+        Box::new(futures::future::ok( data )) as Box<dyn Future<Item = _, Error = _>>
+    });
+
+    let data = call(&router, Method::Head, "/users/mike");
+    assert_eq!(data.response.status(), StatusCode::Ok);
+    assert_eq!(data.response.headers().get::<ContentLength>(), Some(&ContentLength(10)));
+    assert!(body(data).is_empty());
+
+    // A Content-Length set by the handler is kept
+    let data = call(&router, Method::Head, "/sized");
+    assert_eq!(data.response.headers().get::<ContentLength>(), Some(&ContentLength(100)));
+    assert!(body(data).is_empty());
+
+    let data = call(&router, Method::Head, "/groups");
+    assert_eq!(data.response.status(), StatusCode::NotFound);
+}
+
+#[test]
+fn named_routes()
+{
+    let router = Router::new();
+    router.insert_named("user", "/users/:name", Method::Get, by_param);
+    router.insert_named("files", "/files/*rest", Method::Get, by_wildcard);
+    router.insert("/link", Method::Get, |mut data: PluginData<()>| {
+        let url = data.url_for("user", &[("name", "mike")]).unwrap();
+        data.response.set_body(url);
+        data.response.set_status(StatusCode::Ok);
+        Box::new(futures::future::ok( data )) as Box<dyn Future<Item = _, Error = _>>
+    });
+
+    assert_eq!(router.url_for("user", &[("name", "mike dilger")]),
+               Ok("/users/mike%20dilger".to_owned()));
+    assert_eq!(router.url_for("user", &[("name", "a?b#c")]),
+               Ok("/users/a%3Fb%23c".to_owned()));
+    assert_eq!(router.url_for("files", &[("rest", "docs/read me.txt")]),
+               Ok("/files/docs/read%20me.txt".to_owned()));
+    assert_eq!(router.url_for("user", &[]),
+               Err(UrlError::MissingParam { route: "user".to_owned(),
+                                            param: "name".to_owned() }));
+    assert_eq!(router.url_for("group", &[]),
+               Err(UrlError::UnknownRoute("group".to_owned())));
+
+    // Static segments are encoded too, and dot segments are refused
+    router.insert_named("item", "/café/:name", Method::Get, by_param);
+    assert_eq!(router.url_for("item", &[("name", "1")]), Ok("/caf%C3%A9/1".to_owned()));
+    assert_eq!(get(&router, "/caf%C3%A9/1").0, StatusCode::Ok);
+    for value in &["", ".", "..", "a/b", "a\0b"] {
+        assert_eq!(router.url_for("item", &[("name", value)]),
+                   Err(UrlError::InvalidParam { route: "item".to_owned(),
+                                                param: "name".to_owned() }));
+    }
+    assert_eq!(router.url_for("item", &[("name", "...")]), Ok("/caf%C3%A9/...".to_owned()));
+    assert_eq!(router.url_for("files", &[("rest", "docs/../secret")]),
+               Err(UrlError::InvalidParam { route: "files".to_owned(),
+                                            param: "rest".to_owned() }));
+    assert_eq!(router.url_for("files", &[("rest", "a\0b")]),
+               Err(UrlError::InvalidParam { route: "files".to_owned(),
+                                            param: "rest".to_owned() }));
+    assert_eq!(router.url_for("files", &[("rest", "")]), Ok("/files/".to_owned()));
+
+    // The router routes each URL it generates back to the same parameters
+    let urls = [
+        ("user", "name", "mike dilger", "param mike dilger"),
+        ("user", "name", "a?b#c%d", "param a?b#c%d"),
+        ("user", "name", "...", "param ..."),
+        ("item", "name", "café", "param café"),
+        ("files", "rest", "docs/read me.txt", "wildcard docs/read me.txt"),
+        ("files", "rest", "a%2F/b?", "wildcard a%2F/b?"),
+        ("files", "rest", "", "wildcard "),
+    ];
+    for &(name, param, value, body) in &urls {
+        let url = router.url_for(name, &[(param, value)]).unwrap();
+        assert_eq!(get(&router, &url), (StatusCode::Ok, body.to_owned()), "{}", url);
+    }
+
+    assert_eq!(get(&router, "/link"), (StatusCode::Ok, "/users/mike".to_owned()));
+
+    // A name moves to the route it was given to last
+    router.insert_named("x", "/a", Method::Get, by_name);
+    router.insert_named("x", "/b", Method::Get, by_name);
+    router.insert_named("x", "/a", Method::Get, by_name);
+    assert_eq!(router.url_for("x", &[]), Ok("/a".to_owned()));
+    let named = router.routes().into_iter().filter(|r| r.name.as_deref() == Some("x")).count();
+    assert_eq!(named, 1);
+}
+
+fn echo_path(mut data: PluginData<()>)
+             -> Box<dyn Future<Item = PluginData<()>, Error = IoError>>
+{
+    let body = format!("{} {:?}", data.request.path(), data.params.get("org"));
+    data.response.set_body(body);
+    data.response.set_status(StatusCode::Ok);
+    Box::new(futures::future::ok( data ))
+}
+
+#[test]
+fn mounting()
+{
+    let users = Router::new();
+    users.insert("/", Method::Get, echo_path);
+    users.insert_named("member", "/:name", Method::Get, echo_path);
+
+    let router = Router::new();
+    router.insert("/orgs/:org/users/admin", Method::Get, by_name);
+    router.mount("/orgs/:org/users", users);
+
+    assert_eq!(get(&router, "/orgs/rust/users"),
+               (StatusCode::Ok, "/ Some(\"rust\")".to_owned()));
+    assert_eq!(get(&router, "/orgs/rust/users/mike"),
+               (StatusCode::Ok, "/mike Some(\"rust\")".to_owned()));
+    assert_eq!(get(&router, "/orgs/rust/users/admin"), (StatusCode::Ok, "static 1".to_owned()));
+
+    assert_eq!(router.url_for("member", &[("org", "rust"), ("name", "mike")]),
+               Ok("/orgs/rust/users/mike".to_owned()));
+
+    // The outer URI is restored after the mounted router is done
+    let data = call(&router, Method::Get, "/orgs/rust/users/mike?page=2");
+    assert_eq!(data.request.path(), "/orgs/rust/users/mike");
+    assert_eq!(data.request.query(), Some("page=2"));
+
+    // The mounted router decides what is not found
+    assert_eq!(get(&router, "/orgs/rust/users/mike/photos").0, StatusCode::NotFound);
+    let data = call(&router, Method::Post, "/orgs/rust/users/mike");
+    assert_eq!(data.response.status(), StatusCode::MethodNotAllowed);
+
+    // Redirects from a mounted router keep the prefix, even when nested
+    let mut users = Router::new();
+    users.set_trailing_slash(TrailingSlash::Redirect);
+    users.insert("/users", Method::Get, by_name);
+    let api = Router::new();
+    api.mount("/v1", users);
+    let router = Router::new();
+    router.mount("/api", api);
+    let data = call(&router, Method::Get, "/api/v1/users/?page=2");
+    assert_eq!(data.response.status(), StatusCode::PermanentRedirect);
+    assert_eq!(data.response.headers().get::<Location>().map(|l| &**l),
+               Some("/api/v1/users?page=2"));
+    assert_eq!(data.request.path(), "/api/v1/users/");
+    assert_eq!(data.mount_prefix, "");
+}
+
+struct Stamp;
+impl Plugin<(),IoError> for Stamp {
+    fn handle(&self, mut data: PluginData<()>)
+              -> Box<dyn Future<Item = PluginData<()>, Error = IoError>>
+    {
+        data.params.insert("stamped".to_owned(), "yes".to_owned());
+        Box::new(futures::future::ok( data ))
+    }
+}
+
+#[test]
+fn groups()
+{
+    let router = Router::new();
+    {
+        let admin = router.group("/admin", vec![Arc::new(Box::new(Stamp))]);
+        admin.insert("/", Method::Get, by_name);
+        admin.insert("/users/:name", Method::Get, by_param);
+    }
+    router.insert("/public", Method::Get, by_name);
+
+    assert_eq!(get(&router, "/admin"), (StatusCode::Ok, "static 1".to_owned()));
+    assert_eq!(get(&router, "/admin/users/mike"), (StatusCode::Ok, "param mike".to_owned()));
+    assert_eq!(call(&router, Method::Get, "/admin/users/mike").params.get("stamped"),
+               Some(&"yes".to_owned()));
+    assert_eq!(get(&router, "/public"), (StatusCode::Ok, "static 0".to_owned()));
+    assert_eq!(get(&router, "/admin/groups").0, StatusCode::NotFound);
+    assert_eq!(call(&router, Method::Get, "/admin/groups").params.get("stamped"), None);
+}
+
+fn respond(body: &'static str)
+           -> impl Fn(PluginData<()>) -> Box<dyn Future<Item = PluginData<()>, Error = IoError>>
+{
+    move |mut data: PluginData<()>| {
+        data.response.set_body(body);
+        data.response.set_status(StatusCode::Ok);
+        Box::new(futures::future::ok( data )) as Box<dyn Future<Item = _, Error = _>>
+    }
+}
+
+#[test]
+fn guards()
+{
+    let router = Router::new();
+    router.route("/users", Method::Get)
+        .guard(guard::host("api.example.com"))
+        .guard(guard::header_value("Api-Version", "2"))
+        .to(respond("api v2"));
+    router.route("/users", Method::Get)
+        .guard(guard::host("api.example.com"))
+        .to(respond("api"));
+    router.route("/users", Method::Get)
+        .guard(guard::accept("application/json"))
+        .to(respond("json"));
+    router.route("/users/:name", Method::Get)
+        .guard(guard::header("X-Admin"))
+        .to(respond("admin"));
+    router.insert("/users", Method::Get, respond("html"));
+
+    let json = ("Accept", "application/json");
+    let html = ("Accept", "text/html, */*;q=0");
+    assert_eq!(get_with(&router, "/users", &[html]), "html");
+    assert_eq!(get_with(&router, "/users", &[json]), "json");
+    assert_eq!(get_with(&router, "/users", &[("Accept", "application/*")]), "json");
+    assert_eq!(get_with(&router, "/users", &[html, ("Host", "API.example.com:8080")]), "api");
+    assert_eq!(get_with(&router, "/users", &[("Host", "api.example.com"),
+                                             ("Api-Version", "2")]), "api v2");
+    assert_eq!(get_with(&router, "/users", &[html, ("Api-Version", "2")]), "html");
+
+    // A route whose guards fail is not there at all
+    assert_eq!(get_with(&router, "/users/mike", &[("X-Admin", "")]), "admin");
+    assert_eq!(get(&router, "/users/mike").0, StatusCode::NotFound);
+}
+
+#[test]
+fn introspection()
+{
+    let users = Router::new();
+    users.route("/:name", Method::Get).name("user").summary("Show a user").tag("users")
+        .to(by_param);
+
+    let router = Router::new();
+    router.insert("/", Method::Get, by_name);
+    router.mount("/users", users);
+    router.route("/files/*path", Method::Delete).tag("files").tag("admin").to(by_wildcard);
+
+    let routes = router.routes();
+    assert_eq!(routes.len(), 3);
+    assert_eq!((&routes[0].method, &*routes[0].pattern), (&Method::Get, "/"));
+    assert_eq!((&routes[1].method, &*routes[1].pattern), (&Method::Get, "/users/:name"));
+    assert_eq!(routes[1].name, Some("user".to_owned()));
+    assert_eq!(routes[1].summary, Some("Show a user".to_owned()));
+    assert_eq!(routes[1].tags, vec!["users".to_owned()]);
+    assert_eq!((&routes[2].method, &*routes[2].pattern), (&Method::Delete, "/files/*path"));
+    assert_eq!(routes[2].summary, None);
+    assert_eq!(routes[2].tags, vec!["files".to_owned(), "admin".to_owned()]);
+}
+
+#[test]
+fn normalized_paths()
+{
+    let router = Router::new();
+    router.insert("/users/:name", Method::Get, by_param);
+    router.insert("/files/*rest", Method::Get, by_wildcard);
+
+    assert_eq!(get(&router, "/users/caf%C3%A9"), (StatusCode::Ok, "param café".to_owned()));
+    assert_eq!(get(&router, "//users//mike"), (StatusCode::Ok, "param mike".to_owned()));
+    assert_eq!(get(&router, "/files/a/../b.txt"),
+               (StatusCode::Ok, "wildcard b.txt".to_owned()));
+    assert_eq!(get(&router, "/users/a%2Fb").0, StatusCode::NotFound);
+}
+
+#[test]
+fn trailing_slash()
+{
+    let define = |policy| {
+        let mut router = Router::new();
+        router.set_trailing_slash(policy);
+        router.insert("/about", Method::Get, by_name);
+        router.insert("/docs/", Method::Get, by_name);
+        router
+    };
+
+    let router = define(TrailingSlash::Strict);
+    assert_eq!(get(&router, "/about").0, StatusCode::Ok);
+    assert_eq!(get(&router, "/about/").0, StatusCode::NotFound);
+    assert_eq!(get(&router, "/docs").0, StatusCode::NotFound);
+
+    let router = define(TrailingSlash::Equal);
+    assert_eq!(get(&router, "/about/").0, StatusCode::Ok);
+    assert_eq!(get(&router, "/docs").0, StatusCode::Ok);
+    // Other methods on the toggled path are not allowed, rather than not found
+    let data = call(&router, Method::Post, "/about/");
+    assert_eq!(data.response.status(), StatusCode::MethodNotAllowed);
+    assert_eq!(data.response.headers().get::<Allow>(),
+               Some(&Allow(vec![Method::Get, Method::Head, Method::Options])));
+
+    let router = define(TrailingSlash::Redirect);
+    assert_eq!(get(&router, "/about").0, StatusCode::Ok);
+    let data = call(&router, Method::Get, "/about/?lang=en");
+    assert_eq!(data.response.status(), StatusCode::PermanentRedirect);
+    assert_eq!(data.response.headers().get::<Location>().map(|l| &**l), Some("/about?lang=en"));
+    let data = call(&router, Method::Get, "/docs");
+    assert_eq!(data.response.headers().get::<Location>().map(|l| &**l), Some("/docs/"));
+    assert_eq!(get(&router, "/nowhere/").0, StatusCode::NotFound);
+    assert_eq!(call(&router, Method::Post, "/about/").response.status(),
+               StatusCode::MethodNotAllowed);
+
+    // Paths are redirected to their canonical form, not only by the trailing slash
+    let data = call(&router, Method::Get, "//about");
+    assert_eq!(data.response.status(), StatusCode::PermanentRedirect);
+    assert_eq!(data.response.headers().get::<Location>().map(|l| &**l), Some("/about"));
+    let data = call(&router, Method::Get, "/docs/./x/..");
+    assert_eq!(data.response.headers().get::<Location>().map(|l| &**l), Some("/docs/"));
+    let data = call(&router, Method::Get, "/a/../about/");
+    assert_eq!(data.response.headers().get::<Location>().map(|l| &**l), Some("/about"));
+}